@@ -0,0 +1,206 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Address, Network, PrivateKey, Rng, TestRng, ViewKey};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Records how many pool accounts the cached funding blocks fund.
+const POOL_INFO_FILE: &str = "pool_info";
+/// Funds each pool account's public balance, which also pays the fees of its transfers.
+const FUND_PUBLIC_BLOCK: &str = "block_fund_public";
+/// Gives each pool account `records_per_account` private credits records.
+const FUND_PRIVATE_BLOCK: &str = "block_fund_private";
+
+/// The pool keys are derived from this seed, so every run derives the same accounts.
+const POOL_SEED: u64 = 1178951203446120469;
+
+/// A test account other than the genesis one.
+#[derive(Clone)]
+pub(crate) struct Account<N: Network> {
+    pub private_key: PrivateKey<N>,
+    pub view_key: ViewKey<N>,
+    pub address: Address<N>,
+}
+
+impl<N: Network> Account<N> {
+    pub(crate) fn new(private_key: PrivateKey<N>) -> Result<Self> {
+        let view_key = ViewKey::try_from(&private_key).or_fail(ErrorKind::Config, || "deriving a pool view key".into())?;
+        let address = Address::try_from(&private_key).or_fail(ErrorKind::Config, || "deriving a pool address".into())?;
+        Ok(Self { private_key, view_key, address })
+    }
+}
+
+/// Derives the first `size` accounts of the pool.
+pub(crate) fn derive_pool<N: Network>(size: usize) -> Result<Vec<Account<N>>> {
+    (0..size)
+        .map(|i| {
+            let rng = &mut TestRng::fixed(POOL_SEED.wrapping_add(i as u64));
+            let private_key = PrivateKey::<N>::new(rng).or_fail(ErrorKind::Config, || format!("deriving pool account {}", i))?;
+            Account::new(private_key)
+        })
+        .collect()
+}
+
+/// The number of pool accounts funded by the cached funding blocks in `dir`.
+pub(crate) fn pool_size(dir: &str) -> Result<usize> {
+    let info_path = format!("{}/{}", dir, POOL_INFO_FILE);
+    if !Path::new(&info_path).exists() {
+        return Ok(0);
+    }
+    fs::read_to_string(&info_path)?.trim().parse::<usize>().or_fail(ErrorKind::Fixture, || format!("reading {}", info_path))
+}
+
+/// Derives `size` pool accounts and funds each of them from the genesis key: `public_amount` microcredits with
+/// `credits.aleo/transfer_public`, then `records_per_account` records of `private_amount` microcredits each with
+/// `transfer_public_to_private`.
+/// The two funding blocks are cached on top of the fixture ledger, so run this after `create-blocks` and any
+/// `create-mix-block` or `preload`.
+pub(crate) fn fund_pool<N: Network>(size: usize, public_amount: u64, private_amount: u64, records_per_account: usize) -> Result<()> {
+    let transactions_dir = "./transactions";
+    if pool_size(transactions_dir)? > 0 {
+        return Err(Error::new(
+            ErrorKind::Config,
+            format!("The pool is already funded; delete {0}/{1}, {0}/{2} and {0}/{3} to refund it", transactions_dir, POOL_INFO_FILE, FUND_PUBLIC_BLOCK, FUND_PRIVATE_BLOCK),
+        ));
+    }
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, ConsensusMemory<N>>(rng, None, None)?;
+    let pool = derive_pool::<N>(size)?;
+
+    let start = Instant::now();
+    for (block_name, function_name, amount, repeat) in [
+        (FUND_PUBLIC_BLOCK, "transfer_public", public_amount, 1),
+        (FUND_PRIVATE_BLOCK, "transfer_public_to_private", private_amount, records_per_account),
+    ] {
+        let transactions = pool
+            .iter()
+            .enumerate()
+            .flat_map(|account| std::iter::repeat(account).take(repeat))
+            .map(|(i, account)| {
+                let inputs = [account.address.to_string(), format!("{}u64", amount)];
+                ledger
+                    .vm()
+                    .execute(&private_key, ("credits.aleo", function_name), inputs.iter(), None, 0, None, rng)
+                    .or_fail(ErrorKind::Execution, || format!("funding pool account {} with {}", i, function_name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Construct the next block.
+        let block = ledger
+            .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transactions, rng)
+            .or_fail(ErrorKind::Ledger, || format!("preparing {}", block_name))?;
+
+        // Check that the next block is valid.
+        ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || format!("checking {}", block_name))?;
+
+        // Add the funding block to the ledger.
+        ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to {}", block_name))?;
+
+        if block.transactions().num_rejected() > 0 {
+            return Err(Error::new(
+                ErrorKind::Ledger,
+                format!("{} of the {} funding transactions in {} were rejected", block.transactions().num_rejected(), size * repeat, block_name),
+            ));
+        }
+        cache_block(transactions_dir, block_name, &block)?;
+        println!("Cached {} ({} x {} {} microcredits)", block_name, size * repeat, function_name, amount);
+    }
+    let info_path = format!("{}/{}", transactions_dir, POOL_INFO_FILE);
+    fs::write(&info_path, size.to_string()).or_fail(ErrorKind::Io, || format!("writing {}", info_path))?;
+
+    println!("Time elapsed is: {:?}", start.elapsed());
+    Ok(())
+}
+
+/// Replays the cached funding blocks, which must follow the preloaded state. Returns the pool size, or zero if the
+/// pool has not been funded.
+pub(crate) fn load_funding_blocks<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, dir: &str) -> Result<usize> {
+    let size = pool_size(dir)?;
+    if size > 0 {
+        load_cached_block(ledger, dir, FUND_PUBLIC_BLOCK)?;
+        load_cached_block(ledger, dir, FUND_PRIVATE_BLOCK)?;
+    }
+    Ok(size)
+}
+
+/// Creates `num_transfers` `credits.aleo/transfer_public` transactions between random pairs of pool accounts.
+pub(crate) fn pool_transfers<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    pool: &[Account<N>],
+    num_transfers: usize,
+    amount: u64,
+    rng: &mut TestRng,
+) -> Result<Vec<Transaction<N>>> {
+    if pool.len() < 2 {
+        return Err(Error::new(ErrorKind::Config, "Transfers need a funded pool of at least 2 accounts; run `fund-pool` first"));
+    }
+    (0..num_transfers)
+        .map(|i| {
+            let sender = rng.gen_range(0..pool.len());
+            // Pick any other account as the receiver.
+            let receiver = (sender + rng.gen_range(1..pool.len())) % pool.len();
+            let inputs = [pool[receiver].address.to_string(), format!("{}u64", amount)];
+            ledger
+                .vm()
+                .execute(&pool[sender].private_key, ("credits.aleo", "transfer_public"), inputs.iter(), None, 0, None, rng)
+                .or_fail(ErrorKind::Execution, || format!("creating transfer {} from pool account {} to {}", i, sender, receiver))
+        })
+        .collect()
+}
+
+/// Times a block of `num_transfers` public transfers between the funded pool accounts, none of which may be rejected.
+pub(crate) fn spam_transfers<N: Network, C: ConsensusStorage<N>>(snapshot_dir: Option<&str>, num_transfers: usize, dev: Option<u16>) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment, which needs the pool to have been funded with `fund-pool`.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let pool = derive_pool::<N>(pool_size(transactions_dir)?)?;
+
+    // Create the transfers.
+    let r = &mut TestRng::default();
+    let create_start = Instant::now();
+    let transfers = pool_transfers(&ledger, &pool, num_transfers, 1, r)?;
+    println!("Created {} transfers in {:?}", transfers.len(), create_start.elapsed());
+
+    // Start the timer
+    let start = Instant::now();
+
+    // Construct the next block.
+    let transfer_block = ledger
+        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transfers, rng)
+        .or_fail(ErrorKind::Ledger, || "preparing the transfer block".into())?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&transfer_block).or_fail(ErrorKind::Ledger, || "checking the transfer block".into())?;
+
+    // Add the transfer block to the ledger.
+    ledger.advance_to_next_block(&transfer_block).or_fail(ErrorKind::Ledger, || "advancing to the transfer block".into())?;
+
+    // Stop the timer
+    let duration = start.elapsed();
+
+    // Print the duration
+    println!("Time elapsed is: {:?}", duration);
+    println!("Time elapsed per transactions is {:?}", duration / num_transfers.max(1) as u32);
+    println!("Pool accounts: {}", pool.len());
+
+    // Every transfer is funded, so none of them may be rejected.
+    let rejected = transfer_block.transactions().num_rejected();
+    if rejected > 0 {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}/{} transfers were rejected", rejected, num_transfers)));
+    }
+
+    Ok(())
+}
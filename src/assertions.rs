@@ -0,0 +1,49 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::workload::MappingExpectation;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::{Identifier, Network, Plaintext, ProgramID, Value};
+use std::str::FromStr;
+
+/// Reads every expected mapping entry from the finalize store and compares it against the expectation.
+/// Prints each mismatch and returns an error if any expectation does not hold.
+pub(crate) fn check_mapping_expectations<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    expectations: &[MappingExpectation],
+) -> Result<()> {
+    let mut mismatches = 0;
+
+    for expectation in expectations {
+        let describe = || format!("parsing the expectation on {}/{}[{}]", expectation.program_id, expectation.mapping, expectation.key);
+        let program_id = ProgramID::<N>::from_str(&expectation.program_id).or_fail(ErrorKind::Config, describe)?;
+        let mapping_name = Identifier::<N>::from_str(&expectation.mapping).or_fail(ErrorKind::Config, describe)?;
+        let key = Plaintext::<N>::from_str(&expectation.key).or_fail(ErrorKind::Config, describe)?;
+        let expected = expectation.value.as_ref().map(|value| Value::<N>::from_str(value)).transpose().or_fail(ErrorKind::Config, describe)?;
+
+        // Read the confirmed value from the finalize store.
+        let actual = ledger
+            .vm()
+            .finalize_store()
+            .get_value_confirmed(program_id, mapping_name, &key)
+            .or_fail(ErrorKind::Ledger, || format!("reading {}/{}[{}] from the finalize store", expectation.program_id, expectation.mapping, expectation.key))?;
+
+        if actual != expected {
+            mismatches += 1;
+            println!(
+                "Mapping mismatch at {}/{}[{}]: expected {:?}, found {:?}",
+                expectation.program_id,
+                expectation.mapping,
+                expectation.key,
+                expected.map(|v| v.to_string()),
+                actual.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}/{} mapping expectations failed", mismatches, expectations.len())));
+    }
+
+    println!("All {} mapping expectations hold", expectations.len());
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::programs;
+use crate::test_helpers::*;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Network, PrivateKey, TestRng, ToBytes};
+use std::time::{Duration, Instant};
+
+/// Block limits to name as the limiting factor when a block stops fitting, besides `N::MAX_TRANSACTIONS`.
+pub(crate) struct BlockLimits {
+    /// The most `set`/`remove` commands the finalizes of one block may run.
+    pub max_finalize_writes: usize,
+    /// The largest serialized block, in bytes.
+    pub max_block_bytes: usize,
+}
+
+/// How a block of `count` transactions fared.
+enum Trial {
+    /// The block was built and checked with every transaction accepted, within the time budget.
+    Fits { elapsed: Duration, size: usize, finalize_operations: usize },
+    /// The block was built and checked, but some transactions were aborted or rejected.
+    Dropped { accepted: usize, size: usize },
+    /// Building or checking the block took longer than the time budget.
+    OverBudget { elapsed: Duration },
+    /// `prepare_advance_to_next_beacon_block` or `check_next_block` failed. `size` is the serialized size of the
+    /// block, if one was built.
+    Failed { stage: &'static str, error: String, size: Option<usize> },
+}
+
+/// Binary-searches the largest number of cached `transaction_N` fixtures that fit in one block: the block must be
+/// built and checked within `time_budget`, with every transaction accepted. Blocks are only built and checked, never
+/// advanced to, so every trial starts from the same fixture ledger. When a block stops fitting, the first of
+/// `N::MAX_TRANSACTIONS` and `limits` it exceeds is reported as the limiting factor.
+pub(crate) fn find_block_capacity<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    max_transactions: usize,
+    time_budget: Duration,
+    limits: &BlockLimits,
+    dev: Option<u16>,
+) -> Result<()> {
+    let transactions_dir = "./transactions";
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let corpus = list_transactions(transactions_dir)?
+        .into_iter()
+        .take(max_transactions)
+        .map(|(_, path)| read_transaction::<N>(&path))
+        .collect::<Result<Vec<_>>>()?;
+    if corpus.is_empty() {
+        return Err(Error::new(ErrorKind::Fixture, format!("No transaction_N fixtures in {}", transactions_dir)));
+    }
+    println!("Searching up to {} transactions with a {:?} budget", corpus.len(), time_budget);
+
+    // `fits` is known to fit, `limit` is known not to (or is one past the corpus).
+    let (mut fits, mut limit) = (0, corpus.len() + 1);
+    let mut limiting_factor = String::new();
+    let mut count = corpus.len();
+    while fits + 1 < limit {
+        let trial = run_trial(&ledger, &private_key, &corpus[..count], time_budget, rng);
+        match &trial {
+            Trial::Fits { elapsed, size, finalize_operations } => {
+                println!("{:>6} txs: fits in {:?}, {} bytes, {} finalize operations", count, elapsed, size, finalize_operations);
+                fits = count;
+            }
+            Trial::Dropped { accepted, size } => {
+                println!("{:>6} txs: only {} accepted", count, accepted);
+                limiting_factor = exceeded_limit(&ledger, &corpus[..count], Some(*size), limits)
+                    .unwrap_or_else(|| format!("transactions beyond {} were aborted or rejected within every known limit", accepted));
+                limit = count;
+            }
+            Trial::OverBudget { elapsed } => {
+                println!("{:>6} txs: took {:?}, over budget", count, elapsed);
+                limiting_factor = format!("building and checking took longer than {:?}", time_budget);
+                limit = count;
+            }
+            Trial::Failed { stage, error, size } => {
+                println!("{:>6} txs: {} failed: {}", count, stage, error);
+                limiting_factor =
+                    exceeded_limit(&ledger, &corpus[..count], *size, limits).unwrap_or_else(|| format!("{} failed: {}", stage, error));
+                limit = count;
+            }
+        }
+        count = (fits + limit) / 2;
+    }
+
+    println!("Largest block: {} transactions", fits);
+    if fits == corpus.len() {
+        println!("No limit was reached before the search ran out of cached transactions at {}", corpus.len());
+    } else {
+        println!("Limiting factor: {}", limiting_factor);
+    }
+    Ok(())
+}
+
+/// Names the first limit a block of `transactions` exceeds: `N::MAX_TRANSACTIONS`, the summed finalize writes, or
+/// the serialized block size. `block_size` is the size of the built block, if there is one; otherwise the
+/// transactions' own sizes are summed.
+fn exceeded_limit<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    transactions: &[Transaction<N>],
+    block_size: Option<usize>,
+    limits: &BlockLimits,
+) -> Option<String> {
+    if transactions.len() > N::MAX_TRANSACTIONS {
+        return Some(format!("{} transactions exceed N::MAX_TRANSACTIONS ({})", transactions.len(), N::MAX_TRANSACTIONS));
+    }
+    let writes = finalize_writes(ledger, transactions);
+    if writes > limits.max_finalize_writes {
+        return Some(format!("{} finalize writes exceed the block limit of {}", writes, limits.max_finalize_writes));
+    }
+    let size = block_size.unwrap_or_else(|| {
+        transactions.iter().map(|transaction| transaction.to_bytes_le().map(|bytes| bytes.len()).unwrap_or(0)).sum()
+    });
+    if size > limits.max_block_bytes {
+        return Some(format!("{} serialized bytes exceed the block limit of {}", size, limits.max_block_bytes));
+    }
+    None
+}
+
+/// The `set` and `remove` commands the finalizes of `transactions`' executions run, for programs on the ledger.
+fn finalize_writes<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, transactions: &[Transaction<N>]) -> usize {
+    let process = ledger.vm().process();
+    let process = process.read();
+    transactions
+        .iter()
+        .filter_map(|transaction| transaction.execution())
+        .flat_map(|execution| execution.transitions())
+        .filter_map(|transition| process.get_program(transition.program_id()).ok()?.get_function(transition.function_name()).ok())
+        .map(|function| programs::finalize_writes(&function))
+        .sum()
+}
+
+fn run_trial<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    transactions: &[Transaction<N>],
+    time_budget: Duration,
+    rng: &mut TestRng,
+) -> Trial {
+    let start = Instant::now();
+    let block = match ledger.prepare_advance_to_next_beacon_block(private_key, vec![], vec![], transactions.to_vec(), rng) {
+        Ok(block) => block,
+        Err(error) => return Trial::Failed { stage: "prepare_advance_to_next_beacon_block", error: error.to_string(), size: None },
+    };
+    let checked = ledger.check_next_block(&block);
+    let elapsed = start.elapsed();
+    let size = block.to_bytes_le().map(|bytes| bytes.len()).unwrap_or(0);
+    if let Err(error) = checked {
+        return Trial::Failed { stage: "check_next_block", error: error.to_string(), size: Some(size) };
+    }
+
+    let accepted = block.transactions().num_accepted();
+    if accepted < transactions.len() {
+        return Trial::Dropped { accepted, size };
+    }
+    if elapsed > time_budget {
+        return Trial::OverBudget { elapsed };
+    }
+    let finalize_operations = block.transactions().iter().map(|confirmed| confirmed.finalize_operations().len()).sum();
+    Trial::Fits { elapsed, size, finalize_operations }
+}
@@ -0,0 +1,66 @@
+use crate::error::{Error, ErrorKind, Result};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+/// Command-line arguments of the form `<mode> [positional...] [--flag value...]`.
+/// A `--flag` that is not followed by a value is treated as `true`.
+pub(crate) struct Args {
+    pub mode: String,
+    pub positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    pub(crate) fn parse() -> Self {
+        Self::from_iter(std::env::args().skip(1))
+    }
+
+    pub(crate) fn from_iter(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mode = args.next().unwrap_or_else(|| "parallel-spam".to_string());
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+
+        let mut pending: Option<String> = None;
+        for arg in args {
+            if let Some(name) = arg.strip_prefix("--") {
+                if let Some(previous) = pending.replace(name.to_string()) {
+                    flags.insert(previous, "true".to_string());
+                }
+            } else if let Some(name) = pending.take() {
+                flags.insert(name, arg);
+            } else {
+                positional.push(arg);
+            }
+        }
+        if let Some(previous) = pending {
+            flags.insert(previous, "true".to_string());
+        }
+
+        Self { mode, positional, flags }
+    }
+
+    /// Returns the parsed value of `--name`, or `default` if it was not given.
+    pub(crate) fn get<T: FromStr>(&self, name: &str, default: T) -> Result<T>
+    where
+        T::Err: Debug,
+    {
+        match self.flags.get(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|e| Error::new(ErrorKind::Config, format!("Invalid value `{}` for --{}: {:?}", value, name, e))),
+            None => Ok(default),
+        }
+    }
+
+    /// Returns the raw value of `--name`, if it was given.
+    pub(crate) fn get_str(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(|value| value.as_str())
+    }
+
+    /// Returns whether the switch `--name` was given.
+    pub(crate) fn flag(&self, name: &str) -> Result<bool> {
+        self.get(name, false)
+    }
+}
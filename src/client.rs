@@ -0,0 +1,136 @@
+use crate::error::{join_scoped_worker, Error, ErrorKind, OrFail, Result};
+use crate::http::{self, Url};
+use crate::stats::Distribution;
+use crate::test_helpers::*;
+use snarkvm::prelude::Network;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The broadcast endpoint of a node, or of `serve`, running locally with the default port.
+pub(crate) const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:3030/testnet3/transaction/broadcast";
+
+/// How to replay the corpus against an endpoint.
+pub(crate) struct ReplayConfig {
+    /// Target submissions per second, or `None` to submit as fast as the connections allow.
+    pub rate: Option<f64>,
+    /// The number of submissions in flight at once.
+    pub concurrency: usize,
+    /// Where to write one line per submission with its status, latency and response body.
+    pub responses_path: Option<String>,
+}
+
+/// What the endpoint answered to one submission.
+struct Submission {
+    path: String,
+    /// The HTTP status, or `None` if the request failed before a response arrived.
+    status: Option<u16>,
+    latency: Duration,
+    /// The response body, or the reason there is none.
+    body: String,
+}
+
+/// Parses the `--endpoint` URL.
+pub(crate) fn parse_endpoint(endpoint: &str) -> Result<Url> {
+    endpoint.parse().map_err(|error: String| Error::new(ErrorKind::Config, error))
+}
+
+/// POSTs every cached `transaction_N` in `dir` to `endpoint` as JSON, at `config.rate` with up to
+/// `config.concurrency` requests in flight, and reports the answers and their latencies.
+/// Fails if the endpoint refused or failed to answer any of them.
+pub(crate) fn replay_corpus<N: Network>(dir: &str, endpoint: &Url, config: &ReplayConfig) -> Result<()> {
+    let corpus = list_transactions(dir)?;
+    if corpus.is_empty() {
+        return Err(Error::new(ErrorKind::Fixture, format!("No transaction_N fixtures in {}", dir)));
+    }
+    // Encode everything up front so the latencies only cover the requests.
+    let bodies = corpus
+        .iter()
+        .map(|(_, path)| {
+            let transaction = read_transaction::<N>(path)?;
+            let body = serde_json::to_string(&transaction).or_fail(ErrorKind::Fixture, || format!("encoding {} as JSON", path))?;
+            Ok((path.clone(), body))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match config.rate {
+        Some(rate) => println!("Replaying {} transactions to {} at {} tx/s with {} connections", bodies.len(), endpoint.authority, rate, config.concurrency),
+        None => println!("Replaying {} transactions to {} with {} connections", bodies.len(), endpoint.authority, config.concurrency),
+    }
+    let next_index = AtomicUsize::new(0);
+    let start = Instant::now();
+
+    let submissions = thread::scope(|scope| -> Result<Vec<Submission>> {
+        // The `i`-th submission is due at `start + i / rate`.
+        let workers = (0..config.concurrency)
+            .map(|worker| {
+                let (bodies, next_index) = (&bodies, &next_index);
+                thread::Builder::new().name(format!("replay-{}", worker)).spawn_scoped(scope, move || -> Result<Vec<Submission>> {
+                    let mut submissions = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::SeqCst);
+                        let Some((path, body)) = bodies.get(index) else {
+                            return Ok(submissions);
+                        };
+                        if let Some(rate) = config.rate {
+                            let due = start + Duration::from_secs_f64(index as f64 / rate);
+                            thread::sleep(due.saturating_duration_since(Instant::now()));
+                        }
+                        let sent = Instant::now();
+                        let (status, body) = match http::send(endpoint, "POST", Some(body)) {
+                            Ok(response) => (Some(response.status), response.body),
+                            Err(error) => (None, error.to_string()),
+                        };
+                        submissions.push(Submission { path: path.clone(), status, latency: sent.elapsed(), body });
+                    }
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .or_fail(ErrorKind::Thread, || "spawning the replay workers".into())?;
+
+        let mut submissions = Vec::new();
+        for worker in workers {
+            submissions.extend(join_scoped_worker(worker)?);
+        }
+        Ok(submissions)
+    })?;
+    let elapsed = start.elapsed();
+
+    if let Some(responses_path) = &config.responses_path {
+        let mut file = BufWriter::new(File::create(responses_path).or_fail(ErrorKind::Io, || format!("creating {}", responses_path))?);
+        for submission in &submissions {
+            let status = submission.status.map_or("error".to_string(), |status| status.to_string());
+            writeln!(file, "{}\t{}\t{}\t{}", submission.path, status, submission.latency.as_millis(), submission.body)
+                .or_fail(ErrorKind::Io, || format!("writing {}", responses_path))?;
+        }
+        file.flush().or_fail(ErrorKind::Io, || format!("writing {}", responses_path))?;
+    }
+
+    let mut statuses = BTreeMap::new();
+    for submission in &submissions {
+        *statuses.entry(submission.status).or_insert(0usize) += 1;
+    }
+    let accepted = statuses.get(&Some(200)).copied().unwrap_or(0);
+    println!("Submitted {} transactions in {:?} ({:.2} tx/s), {} accepted", submissions.len(), elapsed, submissions.len() as f64 / elapsed.as_secs_f64(), accepted);
+    for (status, count) in &statuses {
+        match status {
+            Some(status) => println!("  status {}: {}", status, count),
+            None => println!("  no response: {}", count),
+        }
+    }
+    Distribution::new(submissions.iter().map(|submission| submission.latency.as_millis() as u64).collect()).print("Latency (ms)");
+    if let Some(responses_path) = &config.responses_path {
+        println!("Responses written to {}", responses_path);
+    }
+
+    if accepted < submissions.len() {
+        return Err(Error::new(
+            ErrorKind::Assertion,
+            format!("{}/{} transactions were not accepted by {}", submissions.len() - accepted, submissions.len(), endpoint.authority),
+        ));
+    }
+    Ok(())
+}
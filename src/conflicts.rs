@@ -0,0 +1,188 @@
+use crate::accounts::{self, Account};
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::records::{self, RecordPool, RecordSpend};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Network, PrivateKey, TestRng, Value};
+use snarkvm::synthesizer::vm::execution_cost;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// What the ledger did with a block holding conflicting transactions.
+enum Outcome {
+    /// `prepare_advance_to_next_beacon_block` refused to build the block.
+    PrepareFailed(String),
+    /// The block was built but `check_next_block` refused it.
+    CheckFailed(String),
+    /// The block was accepted, with this many of the transactions accepted; the rest were aborted or rejected.
+    Advanced { accepted: usize, submitted: usize },
+}
+
+/// Builds conflicting transactions and checks that the ledger never accepts both sides of a conflict:
+///  - the same record spent by two transactions in one block,
+///  - the same transaction twice in one block,
+///  - two transactions sharing a transition,
+///  - a cached `transaction_N` replayed in a second block,
+///  - a block that is already on the ledger offered again.
+/// Each side of a conflict is valid on its own, and exactly one side must be accepted. A block that is accepted must
+/// not contain a transaction, transition or serial number the ledger already has, or any of them twice.
+pub(crate) fn check_conflicts<N: Network, C: ConsensusStorage<N>>(snapshot_dir: Option<&str>, dev: Option<u16>) -> Result<()> {
+    let transactions_dir = "./transactions";
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let mut accounts = vec![Account::new(private_key)?];
+    accounts.extend(accounts::derive_pool::<N>(accounts::pool_size(transactions_dir)?)?);
+    let mut record_pool = RecordPool::scan(&ledger, &accounts)?;
+
+    let r = &mut TestRng::default();
+    let mut violations = Vec::new();
+    // Runs a block of `transactions`, of which exactly `expected` must be accepted.
+    let mut run = |name: &str, transactions: Vec<Transaction<N>>, expected: usize, rng: &mut TestRng| -> Result<()> {
+        let submitted = transactions.len();
+        let accepted = match run_block(&ledger, &private_key, transactions, &mut violations, rng)? {
+            Outcome::PrepareFailed(error) => {
+                println!("{:<28} prepare failed: {}", name, error);
+                0
+            }
+            Outcome::CheckFailed(error) => {
+                println!("{:<28} check failed: {}", name, error);
+                0
+            }
+            Outcome::Advanced { accepted, submitted } => {
+                println!("{:<28} accepted {}/{}", name, accepted, submitted);
+                accepted
+            }
+        };
+        if accepted != expected {
+            violations.push(format!("{}: {}/{} transactions were accepted, expected {}", name, accepted, submitted, expected));
+        }
+        Ok(())
+    };
+
+    // The same record spent twice in one block.
+    match record_pool.take(1)? {
+        Some(inputs) => {
+            let first = records::spend_transaction(&ledger, &accounts, RecordSpend::Split, &inputs, r)?;
+            let second = records::spend_transaction(&ledger, &accounts, RecordSpend::TransferPrivate, &inputs, r)?;
+            run("record spent twice", vec![first, second], 1, rng)?;
+        }
+        None => println!("{:<28} skipped: no unspent records, run `fund-pool` first", "record spent twice"),
+    }
+
+    // The same transaction twice in one block.
+    let transaction = execute_outer_most_call(&ledger, &private_key, r)?;
+    run("duplicate transaction ID", vec![transaction.clone(), transaction], 1, rng)?;
+
+    // Two transactions sharing a transition: the second reuses the first's execution with a fee of its own.
+    let transaction = execute_outer_most_call(&ledger, &private_key, r)?;
+    let refeed = with_new_fee(&ledger, &private_key, &transaction, 1, r)?;
+    run("duplicate transition ID", vec![transaction, refeed], 1, rng)?;
+
+    // A cached transaction replayed in a second block.
+    let cached_path = format!("{}/transaction_0", transactions_dir);
+    if Path::new(&cached_path).exists() {
+        let cached = read_transaction::<N>(&cached_path)?;
+        run("cached transaction", vec![cached.clone()], 1, rng)?;
+        run("cached transaction replayed", vec![cached], 0, rng)?;
+    } else {
+        println!("{:<28} skipped: {} does not exist", "cached transaction replayed", cached_path);
+    }
+
+    // A block that is already on the ledger.
+    let latest = ledger.get_block(ledger.latest_height()).or_fail(ErrorKind::Ledger, || "reading the latest block".into())?;
+    match ledger.check_next_block(&latest) {
+        Ok(()) => violations.push(format!("check_next_block accepted block {} a second time", latest.height())),
+        Err(error) => println!("{:<28} check failed: {}", "block replayed", error),
+    }
+
+    if !violations.is_empty() {
+        for violation in &violations {
+            eprintln!("Consensus violation: {}", violation);
+        }
+        return Err(Error::new(ErrorKind::Assertion, format!("{} conflict checks failed", violations.len())));
+    }
+    println!("Exactly one side of every conflict was accepted");
+    Ok(())
+}
+
+fn execute_outer_most_call<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    rng: &mut TestRng,
+) -> Result<Transaction<N>> {
+    let execute_inputs: Vec<Value<N>> = Vec::new();
+    ledger
+        .vm()
+        .execute(private_key, ("grandfather_spammer.aleo", "outer_most_call"), execute_inputs.into_iter(), None, 0, None, rng)
+        .or_fail(ErrorKind::Execution, || "executing grandfather_spammer.aleo/outer_most_call".into())
+}
+
+/// Pairs the execution of `transaction` with a new public fee paying `priority_fee`, so the result shares every
+/// execution transition with `transaction` but is a valid transaction on its own.
+fn with_new_fee<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    transaction: &Transaction<N>,
+    priority_fee: u64,
+    rng: &mut TestRng,
+) -> Result<Transaction<N>> {
+    let execution = transaction
+        .execution()
+        .cloned()
+        .ok_or_else(|| Error::new(ErrorKind::Execution, format!("{} is not an execution", transaction.id())))?;
+    let describe = || format!("paying a second fee for the execution in {}", transaction.id());
+    let (base_fee, _) = execution_cost(ledger.vm(), &execution).or_fail(ErrorKind::Ledger, describe)?;
+    let execution_id = execution.to_execution_id().or_fail(ErrorKind::Execution, describe)?;
+    let authorization = ledger.vm().authorize_fee_public(private_key, base_fee, priority_fee, execution_id, rng).or_fail(ErrorKind::Execution, describe)?;
+    let fee = ledger.vm().execute_fee_authorization(authorization, None, rng).or_fail(ErrorKind::Execution, describe)?;
+    Transaction::from_execution(execution, Some(fee)).or_fail(ErrorKind::Execution, describe)
+}
+
+/// Tries to build, check and advance to a block of `transactions`. If the block is accepted, records every way in
+/// which it conflicts with itself or the ledger in `violations` before advancing to it.
+fn run_block<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    transactions: Vec<Transaction<N>>,
+    violations: &mut Vec<String>,
+    rng: &mut TestRng,
+) -> Result<Outcome> {
+    let submitted = transactions.len();
+    let block = match ledger.prepare_advance_to_next_beacon_block(private_key, vec![], vec![], transactions, rng) {
+        Ok(block) => block,
+        Err(error) => return Ok(Outcome::PrepareFailed(error.to_string())),
+    };
+    if let Err(error) = ledger.check_next_block(&block) {
+        return Ok(Outcome::CheckFailed(error.to_string()));
+    }
+
+    let mut ids = HashSet::new();
+    let mut transition_ids = HashSet::new();
+    let mut serial_numbers = HashSet::new();
+    let mut accepted = 0;
+    for confirmed in block.transactions().iter().filter(|confirmed| confirmed.is_accepted()) {
+        accepted += 1;
+        let transaction = confirmed.transaction();
+        let id = transaction.id();
+        if !ids.insert(id) || ledger.contains_transaction_id(&id).unwrap_or(false) {
+            violations.push(format!("transaction {} was accepted twice", id));
+        }
+        for transition_id in transaction.transition_ids() {
+            if !transition_ids.insert(*transition_id) || ledger.contains_transition_id(transition_id).unwrap_or(false) {
+                violations.push(format!("transition {} was accepted twice", transition_id));
+            }
+        }
+        for serial_number in transaction.serial_numbers() {
+            if !serial_numbers.insert(*serial_number) || ledger.contains_serial_number(serial_number).unwrap_or(false) {
+                violations.push(format!("serial number {} was spent twice", serial_number));
+            }
+        }
+    }
+
+    ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to block {}", block.height()))?;
+    Ok(Outcome::Advanced { accepted, submitted })
+}
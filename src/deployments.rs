@@ -0,0 +1,193 @@
+use crate::error::{join_scoped_worker, Error, ErrorKind, OrFail, Result};
+use crate::programs;
+use crate::test_helpers::*;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Network, PrivateKey, Program, TestRng, ToBytes};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The shape of a generated `programs::hash_spammer`, written `<functions>x<hashes>`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ProgramShape {
+    pub functions: usize,
+    pub hashes: usize,
+}
+
+impl FromStr for ProgramShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |n: &str| n.parse::<usize>().ok().filter(|n| *n > 0);
+        match s.split_once('x').map(|(functions, hashes)| (parse(functions), parse(hashes))) {
+            Some((Some(functions), Some(hashes))) => Ok(Self { functions, hashes }),
+            _ => Err(format!("expected <functions>x<hashes>, e.g. 4x16, found `{}`", s)),
+        }
+    }
+}
+
+/// What deploying one program cost.
+struct Measurement {
+    program_id: String,
+    functions: usize,
+    /// `vm().deploy`: key synthesis, certificates and the fee.
+    deploy: Duration,
+    /// `vm().check_transaction` on the deployment.
+    verify: Duration,
+    size: usize,
+}
+
+/// Times deploying and verifying a `hash_spammer` of each shape, along with the programs `create-blocks` deploys,
+/// on a fresh ledger. Programs that don't import each other are deployed in parallel on `workers` threads; the
+/// spammer tree is deployed a wave at a time, so each program's imports are on the ledger before it is deployed.
+/// Parallel deployments contend for the CPU, so use `workers = 1` for per-program times comparable across runs.
+/// Fails before deploying anything if a shape is given twice, as both would deploy the same program name.
+pub(crate) fn bench_deployments<N: Network>(shapes: &[ProgramShape], workers: usize) -> Result<()> {
+    for (i, shape) in shapes.iter().enumerate() {
+        if shapes[..i].contains(shape) {
+            return Err(Error::new(ErrorKind::Config, format!("--shapes lists {}x{} more than once", shape.functions, shape.hashes)));
+        }
+    }
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize a ledger without the fixtures, so the spammers can be deployed again.
+    let TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+
+    let mut first_wave = shapes
+        .iter()
+        .map(|shape| {
+            let name = format!("deploy_bench_{}x{}.aleo", shape.functions, shape.hashes);
+            let source = programs::hash_spammer(&name, shape.functions, shape.hashes);
+            (name, source)
+        })
+        .collect::<Vec<_>>();
+    first_wave.push(("child_spammer.aleo".to_string(), programs::child_spammer(16)?));
+    let waves = [
+        first_wave,
+        vec![("parent_spammer.aleo".to_string(), programs::parent_spammer(14))],
+        vec![("grandfather_spammer.aleo".to_string(), programs::grandfather_spammer(2))],
+    ];
+
+    let start = Instant::now();
+    let mut measurements = Vec::new();
+    for (i, wave) in waves.iter().enumerate() {
+        let programs = wave
+            .iter()
+            .map(|(name, source)| Program::<N>::from_str(source).or_fail(ErrorKind::Config, || format!("parsing {}", name)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let wave_start = Instant::now();
+        let (transactions, wave_measurements): (Vec<_>, Vec<_>) = deploy_all(&ledger, &private_key, &programs, workers)?.into_iter().unzip();
+        println!("Wave {}: deployed and verified {} programs in {:?}", i, programs.len(), wave_start.elapsed());
+        measurements.extend(wave_measurements);
+
+        if i + 1 < waves.len() {
+            advance(&ledger, &private_key, transactions, rng)?;
+        }
+    }
+
+    println!();
+    println!("{:<32} {:>9} {:>12} {:>12} {:>10}", "program", "functions", "deploy (ms)", "verify (ms)", "bytes");
+    for measurement in &measurements {
+        println!(
+            "{:<32} {:>9} {:>12} {:>12} {:>10}",
+            measurement.program_id,
+            measurement.functions,
+            measurement.deploy.as_millis(),
+            measurement.verify.as_millis(),
+            measurement.size
+        );
+    }
+    println!("Time elapsed is: {:?}", start.elapsed());
+    Ok(())
+}
+
+/// Deploys and verifies each of `programs` on up to `workers` threads. Returns the deployments in program order.
+fn deploy_all<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    programs: &[Program<N>],
+    workers: usize,
+) -> Result<Vec<(Transaction<N>, Measurement)>> {
+    let next_index = AtomicUsize::new(0);
+    let results = Mutex::new((0..programs.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| -> Result<()> {
+        let handles = (0..workers.min(programs.len()))
+            .map(|worker| {
+                let (next_index, results) = (&next_index, &results);
+                thread::Builder::new().name(format!("deployer-{}", worker)).spawn_scoped(scope, move || -> Result<()> {
+                    let r = &mut TestRng::default();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::SeqCst);
+                        let Some(program) = programs.get(index) else {
+                            return Ok(());
+                        };
+                        let result = deploy_one(ledger, private_key, program, r)?;
+                        results.lock().unwrap_or_else(|poisoned| poisoned.into_inner())[index] = Some(result);
+                    }
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .or_fail(ErrorKind::Thread, || "spawning the deployers".into())?;
+        // Join every worker before reporting the first failure.
+        let joined = handles.into_iter().map(join_scoped_worker).collect::<Vec<_>>();
+        joined.into_iter().collect()
+    })?;
+
+    results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .map(|result| result.ok_or_else(|| Error::new(ErrorKind::Thread, "a deployment was never attempted")))
+        .collect()
+}
+
+fn deploy_one<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    program: &Program<N>,
+    rng: &mut TestRng,
+) -> Result<(Transaction<N>, Measurement)> {
+    let program_id = program.id().to_string();
+
+    let deploy_start = Instant::now();
+    let transaction = ledger.vm().deploy(private_key, program, None, 0, None, rng).or_fail(ErrorKind::Execution, || format!("deploying {}", program_id))?;
+    let deploy = deploy_start.elapsed();
+
+    let verify_start = Instant::now();
+    ledger.vm().check_transaction(&transaction, None, rng).or_fail(ErrorKind::Ledger, || format!("verifying the deployment of {}", program_id))?;
+    let verify = verify_start.elapsed();
+
+    let size = transaction.to_bytes_le().or_fail(ErrorKind::Execution, || format!("serializing the deployment of {}", program_id))?.len();
+    let functions = program.functions().len();
+    Ok((transaction, Measurement { program_id, functions, deploy, verify, size }))
+}
+
+/// Advances to a block with `transactions`, so the next wave can import the programs they deploy.
+fn advance<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    transactions: Vec<Transaction<N>>,
+    rng: &mut TestRng,
+) -> Result<()> {
+    let num_transactions = transactions.len();
+    let block = ledger
+        .prepare_advance_to_next_beacon_block(private_key, vec![], vec![], transactions, rng)
+        .or_fail(ErrorKind::Ledger, || "preparing a deployment block".into())?;
+    ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || "checking a deployment block".into())?;
+    ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || "advancing to a deployment block".into())?;
+    if block.transactions().num_accepted() < num_transactions {
+        return Err(Error::new(
+            ErrorKind::Ledger,
+            format!("Only {}/{} deployments were accepted", block.transactions().num_accepted(), num_transactions),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::any::Any;
+use std::fmt;
+use std::thread::{JoinHandle, ScopedJoinHandle};
+
+/// The category of a failure, which decides the process exit code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorKind {
+    /// Bad command-line arguments, backend or workload definition.
+    Config,
+    /// Filesystem errors outside of reading fixtures.
+    Io,
+    /// A cached block, transaction or snapshot could not be read or decoded.
+    Fixture,
+    /// `vm().execute` or `vm().deploy` failed to produce a transaction.
+    Execution,
+    /// The ledger failed to prepare, check or advance to a block.
+    Ledger,
+    /// A worker thread could not be spawned or panicked.
+    Thread,
+    /// The run completed but the resulting state was not the expected one.
+    Assertion,
+}
+
+impl ErrorKind {
+    pub(crate) fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Config => 2,
+            ErrorKind::Io => 3,
+            ErrorKind::Fixture => 4,
+            ErrorKind::Execution => 5,
+            ErrorKind::Ledger => 6,
+            ErrorKind::Thread => 7,
+            ErrorKind::Assertion => 8,
+        }
+    }
+}
+
+/// A failure along with what we were doing when it happened, e.g. which fixture file, thread or transaction.
+#[derive(Debug)]
+pub(crate) struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    // Innermost first.
+    pub context: Vec<String>,
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        Self { kind, message: message.to_string(), context: Vec::new() }
+    }
+
+    pub(crate) fn with_context(mut self, context: impl fmt::Display) -> Self {
+        self.context.push(context.to_string());
+        self
+    }
+
+    /// Prints the failure report that ends a failed run.
+    pub(crate) fn print_summary(&self, mode: &str) {
+        eprintln!("==================================================================");
+        eprintln!("Mode `{}` failed", mode);
+        eprintln!("Category: {:?} (exit code {})", self.kind, self.kind.exit_code());
+        eprintln!("Error: {}", self.message);
+        for context in &self.context {
+            eprintln!("  while {}", context);
+        }
+        eprintln!("==================================================================");
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for context in &self.context {
+            write!(f, ", while {}", context)?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns any displayable error (e.g. snarkVM's `anyhow` errors) into an `Error` of the given kind.
+pub(crate) trait OrFail<T> {
+    fn or_fail(self, kind: ErrorKind, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T, E: fmt::Display> OrFail<T> for std::result::Result<T, E> {
+    fn or_fail(self, kind: ErrorKind, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|e| Error::new(kind, e).with_context(context()))
+    }
+}
+
+/// Adds context to an `Error` that already has a kind.
+pub(crate) trait Context<T> {
+    fn context(self, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T> Context<T> for Result<T> {
+    fn context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|e| e.with_context(context()))
+    }
+}
+
+/// Joins a worker thread, turning a panic into a `Thread` error.
+pub(crate) fn join_worker<T>(handle: JoinHandle<Result<T>>) -> Result<T> {
+    let name = handle.thread().name().unwrap_or("unnamed").to_string();
+    joined(&name, handle.join())
+}
+
+/// Joins a worker thread spawned with `thread::scope`, turning a panic into a `Thread` error.
+pub(crate) fn join_scoped_worker<T>(handle: ScopedJoinHandle<Result<T>>) -> Result<T> {
+    let name = handle.thread().name().unwrap_or("unnamed").to_string();
+    joined(&name, handle.join())
+}
+
+fn joined<T>(name: &str, result: std::thread::Result<Result<T>>) -> Result<T> {
+    match result {
+        Ok(result) => result.context(|| format!("running thread `{}`", name)),
+        Err(panic) => Err(Error::new(ErrorKind::Thread, panic_message(&panic)).with_context(format!("running thread `{}`", name))),
+    }
+}
+
+/// Runs `f`, turning a panic into a `Thread` error so the caller can decide whether to carry on.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(panic) => Err(Error::new(ErrorKind::Thread, panic_message(&panic))),
+    }
+}
+
+fn panic_message(panic: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "worker panicked".to_string()
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Identifier, Network, Program, ProgramID, TestRng, Value};
+use snarkvm::synthesizer::program::{CallOperator, Instruction};
+use snarkvm::synthesizer::vm::{cost_in_microcredits, deployment_cost, execution_cost};
+use std::str::FromStr;
+
+/// The finalize cost of calling `program_id/function_name`, summed over the function and everything it calls,
+/// along with the number of transitions the call produces. This is what the fee's finalize part will be.
+pub(crate) fn static_finalize_cost<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    program_id: &ProgramID<N>,
+    function_name: &Identifier<N>,
+) -> Result<(u64, usize)> {
+    let describe = || format!("estimating the finalize cost of {}/{}", program_id, function_name);
+    let program = ledger.vm().process().read().get_program(program_id).or_fail(ErrorKind::Config, describe)?.clone();
+    let function = program.get_function(function_name).or_fail(ErrorKind::Config, describe)?;
+
+    let mut cost = match function.finalize_logic() {
+        Some(finalize) => cost_in_microcredits(finalize).or_fail(ErrorKind::Config, describe)?,
+        None => 0,
+    };
+    let mut transitions = 1;
+    for instruction in function.instructions() {
+        if let Instruction::Call(call) = instruction {
+            let (callee_program, callee_function) = match call.operator() {
+                CallOperator::Locator(locator) => (*locator.program_id(), *locator.resource()),
+                CallOperator::Resource(resource) => (*program_id, *resource),
+            };
+            // Closures run inside the caller's transition and have no finalize.
+            if !ledger.vm().process().read().get_program(&callee_program).map_or(false, |p| p.contains_function(&callee_function)) {
+                continue;
+            }
+            let (callee_cost, callee_transitions) = static_finalize_cost(ledger, &callee_program, &callee_function)?;
+            cost += callee_cost;
+            transitions += callee_transitions;
+        }
+    }
+    Ok((cost, transitions))
+}
+
+/// Estimates what one execution of `locator` (`program/function`) with `inputs` costs on the fixture ledger by
+/// executing it once, and cross-checks the finalize part against `static_finalize_cost`. With `check_corpus`, also
+/// checks that every cached `transaction_N` whose root is `locator` paid exactly the estimate.
+pub(crate) fn estimate_execution<N: Network>(locator: &str, inputs: &[String], check_corpus: bool) -> Result<()> {
+    let (program_name, function_name) = locator
+        .split_once('/')
+        .ok_or_else(|| Error::new(ErrorKind::Config, format!("Expected program/function, found `{}`", locator)))?;
+    let program_id = ProgramID::<N>::from_str(program_name).or_fail(ErrorKind::Config, || format!("parsing {}", program_name))?;
+    let function_id = Identifier::<N>::from_str(function_name).or_fail(ErrorKind::Config, || format!("parsing {}", function_name))?;
+    let inputs = inputs
+        .iter()
+        .map(|input| Value::<N>::from_str(input).or_fail(ErrorKind::Config, || format!("parsing input `{}`", input)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Initialize the test environment.
+    let rng = &mut TestRng::fixed(6404264900108107703);
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, ConsensusMemory<N>>(rng, None, None)?;
+
+    let (static_finalize, transitions) = static_finalize_cost(&ledger, &program_id, &function_id)?;
+
+    // The storage part depends on the size of the execution, so build one.
+    let transaction = ledger
+        .vm()
+        .execute(&private_key, (program_name, function_name), inputs.into_iter(), None, 0, None, rng)
+        .or_fail(ErrorKind::Execution, || format!("executing {}", locator))?;
+    let execution = transaction.execution().ok_or_else(|| Error::new(ErrorKind::Execution, "execute did not produce an execution"))?;
+    let (total, (storage, finalize)) = execution_cost(ledger.vm(), execution).or_fail(ErrorKind::Ledger, || format!("computing the cost of {}", locator))?;
+
+    println!("Estimate for {}:", locator);
+    println!("  transitions:      {}", transitions);
+    println!("  storage cost:     {} microcredits", storage);
+    println!("  finalize cost:    {} microcredits", finalize);
+    println!("  total base fee:   {} microcredits", total);
+    if finalize != static_finalize {
+        return Err(Error::new(
+            ErrorKind::Assertion,
+            format!("The finalize instructions of {} cost {} microcredits, but the execution was charged {}", locator, static_finalize, finalize),
+        ));
+    }
+
+    if check_corpus {
+        check_corpus_fees(&program_id, &function_id, total)?;
+    }
+    Ok(())
+}
+
+/// Checks that the cached transactions rooted at `program_id/function_name` paid `expected` microcredits each.
+fn check_corpus_fees<N: Network>(program_id: &ProgramID<N>, function_name: &Identifier<N>, expected: u64) -> Result<()> {
+    let (mut checked, mut mismatches) = (0, 0);
+    for (_, path) in list_transactions("./transactions")? {
+        let transaction = read_transaction::<N>(&path)?;
+        // `Transaction::transitions` ends with the fee, so take the root from the execution.
+        let root = transaction.execution().and_then(|execution| execution.transitions().last());
+        let is_match = root.map_or(false, |root| root.program_id() == program_id && root.function_name() == function_name);
+        if !is_match {
+            continue;
+        }
+        checked += 1;
+        let paid = match transaction.fee_transition() {
+            Some(fee) => *fee.amount().or_fail(ErrorKind::Fixture, || format!("reading the fee of {}", path))?,
+            None => 0,
+        };
+        if paid != expected {
+            mismatches += 1;
+            println!("{} paid {} microcredits, estimated {}", path, paid, expected);
+        }
+    }
+    println!("Checked the fees of {} cached transactions", checked);
+    if checked == 0 {
+        return Err(Error::new(ErrorKind::Fixture, format!("No cached transactions execute {}/{}, so there was nothing to check", program_id, function_name)));
+    }
+    if mismatches > 0 {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}/{} cached transactions did not pay the estimate", mismatches, checked)));
+    }
+    Ok(())
+}
+
+/// Estimates what deploying the program in `source` costs, without adding it to the ledger.
+pub(crate) fn estimate_deployment<N: Network>(source: &str) -> Result<()> {
+    let program = Program::<N>::from_str(source).or_fail(ErrorKind::Config, || "parsing the program to deploy".into())?;
+
+    // Initialize the test environment, so imports of the fixture programs resolve.
+    let rng = &mut TestRng::fixed(6404264900108107703);
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, ConsensusMemory<N>>(rng, None, None)?;
+
+    let transaction = ledger
+        .vm()
+        .deploy(&private_key, &program, None, 0, None, rng)
+        .or_fail(ErrorKind::Execution, || format!("deploying {}", program.id()))?;
+    let Transaction::Deploy(_, _, deployment, _) = &transaction else {
+        return Err(Error::new(ErrorKind::Execution, "deploy did not produce a deployment"));
+    };
+    let (total, (storage, namespace)) = deployment_cost(deployment).or_fail(ErrorKind::Ledger, || format!("computing the cost of deploying {}", program.id()))?;
+
+    println!("Estimate for deploying {}:", program.id());
+    println!("  storage cost:     {} microcredits", storage);
+    println!("  namespace cost:   {} microcredits", namespace);
+    println!("  total base fee:   {} microcredits", total);
+    Ok(())
+}
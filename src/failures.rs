@@ -0,0 +1,53 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Records jobs that failed during transaction generation so the run can carry on without them.
+///
+/// Each line of the log holds the job's RNG seed, thread and job index along with the error, which is enough to
+/// rerun that one job with `reproduce-job --seed <seed>`. Once more than `max_failures` jobs have failed, `record`
+/// returns an error and the run is aborted.
+pub(crate) struct FailureLog {
+    path: String,
+    file: Mutex<File>,
+    failures: AtomicUsize,
+    max_failures: usize,
+}
+
+impl FailureLog {
+    /// Opens `path` for appending, so failures from earlier runs are kept.
+    pub(crate) fn open(path: &str, max_failures: usize) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path).or_fail(ErrorKind::Io, || format!("opening {}", path))?;
+        Ok(Self { path: path.to_string(), file: Mutex::new(file), failures: AtomicUsize::new(0), max_failures })
+    }
+
+    /// Logs the failed job, and returns an error if the failure budget is now exhausted.
+    pub(crate) fn record(&self, seed: u64, thread_id: usize, job: usize, error: &Error) -> Result<()> {
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        {
+            let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            writeln!(file, "seed={} thread={} job={} kind={:?} error={}", seed, thread_id, job, error.kind, error)
+                .or_fail(ErrorKind::Io, || format!("writing to {}", self.path))?;
+        }
+        eprintln!("Thread {} job {} failed (seed {}): {}", thread_id, job, seed, error);
+
+        if failures > self.max_failures {
+            return Err(Error::new(
+                ErrorKind::Execution,
+                format!("{} jobs failed, more than the --max-failures budget of {}; see {}", failures, self.max_failures, self.path),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The number of failures recorded by this run.
+    pub(crate) fn failures(&self) -> usize {
+        self.failures.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+}
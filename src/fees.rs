@@ -0,0 +1,207 @@
+use crate::accounts::Account;
+use crate::cli::Args;
+use crate::error::{Context, Error, ErrorKind, OrFail, Result};
+use crate::records::{OwnedRecord, RecordPool};
+use crate::test_helpers::*;
+use crate::workload;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::{Block, ConfirmedTransaction, Transaction};
+use snarkvm::prelude::{Address, Identifier, Literal, Network, Plaintext, ProgramID, Record, TestRng, Value};
+use snarkvm::synthesizer::vm::{deployment_cost, execution_cost};
+use std::str::FromStr;
+
+/// How generated transactions pay their fees.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct FeeConfig {
+    /// Pay from a credits record of the caller instead of its public balance.
+    pub private: bool,
+    /// Microcredits paid on top of the base fee.
+    pub priority_fee: u64,
+}
+
+impl FeeConfig {
+    /// Reads `--fee public|private` (default public) and `--priority-fee` (default 0).
+    pub(crate) fn from_args(args: &Args) -> Result<Self> {
+        let private = match args.get_str("fee").unwrap_or("public") {
+            "public" => false,
+            "private" => true,
+            fee => return Err(Error::new(ErrorKind::Config, format!("Unknown fee type `{}`; expected public or private", fee))),
+        };
+        Ok(Self { private, priority_fee: args.get("priority-fee", 0)? })
+    }
+
+    /// The fee record to pass to `execute` or `deploy` for a transaction of the account at index `owner` in `records`,
+    /// or `None` to pay publicly.
+    pub(crate) fn fee_record<N: Network>(&self, records: &mut RecordPool<N>, owner: usize) -> Result<Option<Record<N, Plaintext<N>>>> {
+        if !self.private {
+            return Ok(None);
+        }
+        match records.take_from(owner, 1)? {
+            Some(mut fee_records) => Ok(fee_records.pop().map(|OwnedRecord { record, .. }| record)),
+            None => Err(Error::new(ErrorKind::Config, format!("Account {} has no records left to pay a private fee with", owner))),
+        }
+    }
+}
+
+/// The public `credits.aleo/account` balance of `address`, in microcredits.
+pub(crate) fn public_balance<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, address: &Address<N>) -> Result<u64> {
+    let describe = || format!("reading the public balance of {}", address);
+    let program_id = ProgramID::<N>::from_str("credits.aleo").or_fail(ErrorKind::Config, describe)?;
+    let mapping = Identifier::<N>::from_str("account").or_fail(ErrorKind::Config, describe)?;
+    let key = Plaintext::<N>::from_str(&address.to_string()).or_fail(ErrorKind::Config, describe)?;
+    match ledger.vm().finalize_store().get_value_confirmed(program_id, mapping, &key).or_fail(ErrorKind::Ledger, describe)? {
+        Some(Value::Plaintext(Plaintext::Literal(Literal::U64(balance), _))) => Ok(*balance),
+        None => Ok(0),
+        Some(value) => Err(Error::new(ErrorKind::Ledger, format!("Unexpected balance `{}` for {}", value, address))),
+    }
+}
+
+/// The public balance of `account` and the total of its unspent records, in microcredits.
+pub(crate) fn balances<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, account: &Account<N>) -> Result<(u64, u64)> {
+    let mut records = RecordPool::scan(ledger, std::slice::from_ref(account))?;
+    let mut private = 0;
+    while let Some(record) = records.take_from(0, 1)? {
+        private += record[0].microcredits()?;
+    }
+    Ok((public_balance(ledger, &account.address)?, private))
+}
+
+/// What one transaction paid, and what the base part of it was for.
+pub(crate) struct FeeBreakdown {
+    pub id: String,
+    pub paid: u64,
+    // Storage cost of the transaction's bytes.
+    pub storage: u64,
+    // Finalize cost for executions, namespace cost for deployments.
+    pub other: u64,
+    // The transaction was rejected, so only its fee was kept.
+    pub rejected: bool,
+}
+
+impl FeeBreakdown {
+    pub(crate) fn new<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, transaction: &Transaction<N>) -> Result<Self> {
+        let describe = || format!("computing the cost of {}", transaction.id());
+        let (storage, other) = match transaction {
+            Transaction::Deploy(_, _, deployment, _) => deployment_cost(deployment).or_fail(ErrorKind::Ledger, describe)?.1,
+            Transaction::Execute(_, execution, _) => execution_cost(ledger.vm(), execution).or_fail(ErrorKind::Ledger, describe)?.1,
+            Transaction::Fee(..) => (0, 0),
+        };
+        let paid = match transaction.fee_transition() {
+            Some(fee) => *fee.amount().or_fail(ErrorKind::Ledger, describe)?,
+            None => 0,
+        };
+        Ok(Self { id: transaction.id().to_string(), paid, storage, other, rejected: false })
+    }
+
+    /// What a confirmed transaction paid. A rejected one still pays its fee, whose base cost is that of the rejected
+    /// deployment or execution.
+    pub(crate) fn confirmed<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, confirmed: &ConfirmedTransaction<N>) -> Result<Self> {
+        let rejected = match confirmed {
+            ConfirmedTransaction::RejectedDeploy(_, _, rejected, _) | ConfirmedTransaction::RejectedExecute(_, _, rejected, _) => rejected,
+            _ => return Self::new(ledger, confirmed.transaction()),
+        };
+        // The confirmed transaction is the fee transaction that replaced the rejected one.
+        let mut fee = Self::new(ledger, confirmed.transaction())?;
+        let describe = || format!("computing the cost of the transaction rejected for {}", fee.id);
+        let (storage, other) = match (rejected.deployment(), rejected.execution()) {
+            (Some(deployment), _) => deployment_cost(deployment).or_fail(ErrorKind::Ledger, describe)?.1,
+            (_, Some(execution)) => execution_cost(ledger.vm(), execution).or_fail(ErrorKind::Ledger, describe)?.1,
+            (None, None) => (0, 0),
+        };
+        fee.storage = storage;
+        fee.other = other;
+        fee.rejected = true;
+        Ok(fee)
+    }
+
+    /// Whatever was paid beyond the base cost.
+    pub(crate) fn priority(&self) -> u64 {
+        self.paid.saturating_sub(self.storage + self.other)
+    }
+}
+
+/// Breaks down the fees of every transaction in `block`, accepted or rejected.
+pub(crate) fn block_fees<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, block: &Block<N>) -> Result<Vec<FeeBreakdown>> {
+    block.transactions().iter().map(|confirmed| FeeBreakdown::confirmed(ledger, confirmed)).collect()
+}
+
+/// Prints each transaction's fees and the totals. The fees of rejected transactions are also shown on their own.
+pub(crate) fn print_fee_report(fees: &[FeeBreakdown]) {
+    println!("{:<64} {:>12} {:>12} {:>12} {:>12} {:>12}", "transaction", "paid", "storage", "finalize", "priority", "rejected");
+    let rejected_paid = |fee: &FeeBreakdown| if fee.rejected { fee.paid } else { 0 };
+    for fee in fees {
+        println!("{:<64} {:>12} {:>12} {:>12} {:>12} {:>12}", fee.id, fee.paid, fee.storage, fee.other, fee.priority(), rejected_paid(fee));
+    }
+    let total = |f: &dyn Fn(&FeeBreakdown) -> u64| fees.iter().map(f).sum::<u64>();
+    println!(
+        "{:<64} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "total",
+        total(&|fee| fee.paid),
+        total(&|fee| fee.storage),
+        total(&|fee| fee.other),
+        total(&FeeBreakdown::priority),
+        total(&rejected_paid)
+    );
+}
+
+/// Times a block of `num_transactions` `outer_most_call` executions paying fees as `fee_config` says, reports what
+/// they paid, and checks the genesis account lost exactly that.
+pub(crate) fn spam_fees<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    fee_config: FeeConfig,
+    num_transactions: usize,
+    dev: Option<u16>,
+) -> Result<()> {
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let genesis = Account::new(private_key)?;
+    let mut records = RecordPool::scan(&ledger, std::slice::from_ref(&genesis))?;
+    let (public_before, private_before) = balances(&ledger, &genesis)?;
+
+    // Create the fee-paying transactions.
+    let r = &mut TestRng::default();
+    let workload = workload::outer_most_call();
+    let mut transactions = Vec::new();
+    for i in 0..num_transactions {
+        let execute_inputs = workload.sample_inputs::<N>(r)?;
+        let fee_record = fee_config.fee_record(&mut records, 0).context(|| format!("paying for transaction {}", i))?;
+        let new_tx = ledger
+            .vm()
+            .execute(&private_key, (workload.program_id.as_str(), workload.function_name.as_str()), execute_inputs.into_iter(), fee_record, fee_config.priority_fee, None, r)
+            .or_fail(ErrorKind::Execution, || format!("executing fee-paying transaction {}", i))?;
+        transactions.push(new_tx);
+        println!("{}/{} completed!", i + 1, num_transactions);
+    }
+
+    // Construct the next block.
+    let fee_block = ledger
+        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transactions, rng)
+        .or_fail(ErrorKind::Ledger, || "preparing the fee-paying block".into())?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&fee_block).or_fail(ErrorKind::Ledger, || "checking the fee-paying block".into())?;
+
+    // Add the block to the ledger.
+    ledger.advance_to_next_block(&fee_block).or_fail(ErrorKind::Ledger, || "advancing to the fee-paying block".into())?;
+
+    // Report what the transactions paid and what it did to the genesis account.
+    let fee_breakdown = block_fees(&ledger, &fee_block)?;
+    print_fee_report(&fee_breakdown);
+    let (public_after, private_after) = balances(&ledger, &genesis)?;
+    println!("Genesis public balance: {} -> {} microcredits", public_before, public_after);
+    println!("Genesis private balance: {} -> {} microcredits", private_before, private_after);
+
+    // The genesis account should only have lost what it paid in fees. Staking rewards for the blocks it produced
+    // are bonded rather than credited, so they don't show up here.
+    let paid = fee_breakdown.iter().map(|fee| fee.paid).sum::<u64>();
+    let spent = (public_before + private_before) as i128 - (public_after + private_after) as i128;
+    println!("Fees burned: {} microcredits", paid);
+    if spent != paid as i128 {
+        return Err(Error::new(ErrorKind::Assertion, format!("The genesis account lost {} microcredits but paid {} in fees", spent, paid)));
+    }
+
+    Ok(())
+}
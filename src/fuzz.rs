@@ -0,0 +1,136 @@
+use crate::error::{catch_panic, Error, ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{FromBytes, Network, Rng, TestRng, ToBytes};
+use std::fs;
+use std::time::Instant;
+
+/// A way of corrupting a serialized transaction.
+#[derive(Copy, Clone, Debug)]
+enum Mutation {
+    /// Flip one random bit.
+    BitFlip,
+    /// Cut the bytes off at a random length.
+    Truncate,
+    /// Overwrite a random range with the bytes at the same offset in another transaction.
+    Splice,
+}
+
+impl Mutation {
+    const ALL: [Mutation; 3] = [Mutation::BitFlip, Mutation::Truncate, Mutation::Splice];
+
+    /// Returns `bytes` mutated. Empty `bytes` have nothing to mutate and come back as they are, and a splice with an
+    /// empty donor flips a bit instead.
+    fn apply(self, bytes: &[u8], donor: &[u8], rng: &mut TestRng) -> Vec<u8> {
+        let mut mutated = bytes.to_vec();
+        if bytes.is_empty() {
+            return mutated;
+        }
+        match self {
+            Mutation::BitFlip => {
+                let bit = rng.gen_range(0..bytes.len() * 8);
+                mutated[bit / 8] ^= 1 << (bit % 8);
+            }
+            Mutation::Truncate => mutated.truncate(rng.gen_range(0..bytes.len())),
+            Mutation::Splice => {
+                let len = bytes.len().min(donor.len());
+                if len == 0 {
+                    return Mutation::BitFlip.apply(bytes, donor, rng);
+                }
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len.min(start + 64));
+                mutated[start..end].copy_from_slice(&donor[start..end]);
+            }
+        }
+        mutated
+    }
+}
+
+/// What became of one mutated input.
+enum Verdict {
+    /// `read_le` refused the bytes, or the VM refused the transaction.
+    Rejected,
+    /// The mutation did not change the transaction, e.g. a splice of identical bytes.
+    Unchanged,
+    /// Decoding or checking the transaction panicked.
+    Crashed(String),
+    /// The VM accepted a transaction that differs from any we generated.
+    Accepted,
+}
+
+/// Mutates the cached `transaction_N` fixtures `iterations` times and checks that every mutant is either refused by
+/// `Transaction::read_le` or by `check_transaction`. Mutants that panic or are accepted are saved to `reproducer_dir`.
+pub(crate) fn fuzz_transactions<N: Network>(iterations: usize, seed: u64, reproducer_dir: &str) -> Result<()> {
+    let transactions_dir = "./transactions";
+    let corpus = list_transactions(transactions_dir)?
+        .into_iter()
+        .map(|(_, path)| {
+            let bytes = fs::read(&path).or_fail(ErrorKind::Fixture, || format!("reading {}", path))?;
+            Ok((path, bytes))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if corpus.len() < 2 {
+        return Err(Error::new(ErrorKind::Fixture, format!("Fuzzing needs at least 2 transactions in {}", transactions_dir)));
+    }
+
+    // Initialize the test environment.
+    let env_rng = &mut TestRng::fixed(6404264900108107703);
+    let TestEnv { ledger, .. } = load_fixture_env::<N, ConsensusMemory<N>>(env_rng, None, None)?;
+
+    println!("Fuzzing {} transactions for {} iterations with seed {}", corpus.len(), iterations, seed);
+    let rng = &mut TestRng::fixed(seed);
+    let start = Instant::now();
+    let (mut rejected, mut unchanged, mut findings) = (0, 0, 0);
+    for iteration in 0..iterations {
+        let (path, bytes) = &corpus[rng.gen_range(0..corpus.len())];
+        let (_, donor) = &corpus[rng.gen_range(0..corpus.len())];
+        let mutation = Mutation::ALL[rng.gen_range(0..Mutation::ALL.len())];
+        let mutated = mutation.apply(bytes, donor, rng);
+
+        let verdict = match catch_panic(|| Ok(Transaction::<N>::read_le(&mutated[..]))) {
+            Err(panic) => Verdict::Crashed(format!("read_le panicked: {}", panic)),
+            Ok(Err(_)) => Verdict::Rejected,
+            Ok(Ok(transaction)) if transaction.to_bytes_le().ok().as_deref() == Some(&bytes[..]) => Verdict::Unchanged,
+            Ok(Ok(transaction)) => match catch_panic(|| Ok(ledger.vm().check_transaction(&transaction, None, rng))) {
+                Err(panic) => Verdict::Crashed(format!("check_transaction panicked: {}", panic)),
+                Ok(Err(_)) => Verdict::Rejected,
+                Ok(Ok(())) => Verdict::Accepted,
+            },
+        };
+
+        match verdict {
+            Verdict::Rejected => rejected += 1,
+            Verdict::Unchanged => unchanged += 1,
+            Verdict::Crashed(reason) => {
+                findings += 1;
+                save_reproducer(reproducer_dir, iteration, path, mutation, &mutated, &reason)?;
+            }
+            Verdict::Accepted => {
+                findings += 1;
+                save_reproducer(reproducer_dir, iteration, path, mutation, &mutated, "check_transaction accepted a mutant")?;
+            }
+        }
+        if (iteration + 1) % 100 == 0 {
+            println!("{}/{} iterations, {} findings", iteration + 1, iterations, findings);
+        }
+    }
+
+    println!("Time elapsed is: {:?}", start.elapsed());
+    println!("Rejected: {}, unchanged: {}, findings: {}", rejected, unchanged, findings);
+    if findings > 0 {
+        return Err(Error::new(ErrorKind::Assertion, format!("{} mutants crashed or were accepted; see {}", findings, reproducer_dir)));
+    }
+    Ok(())
+}
+
+/// Saves a mutant as `<reproducer_dir>/<iteration>_<mutation>`, along with a note on where it came from.
+fn save_reproducer(reproducer_dir: &str, iteration: usize, source: &str, mutation: Mutation, bytes: &[u8], reason: &str) -> Result<()> {
+    fs::create_dir_all(reproducer_dir).or_fail(ErrorKind::Io, || format!("creating {}", reproducer_dir))?;
+    let path = format!("{}/{}_{:?}", reproducer_dir, iteration, mutation);
+    fs::write(&path, bytes).or_fail(ErrorKind::Io, || format!("writing {}", path))?;
+    fs::write(format!("{}.txt", path), format!("source: {}\nmutation: {:?}\nreason: {}\n", source, mutation, reason))
+        .or_fail(ErrorKind::Io, || format!("writing {}.txt", path))?;
+    eprintln!("Saved reproducer {} ({}): {}", path, source, reason);
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::str::FromStr;
+
+/// The largest body we read, so a bad peer can't make us allocate without bound. A deployment is well under this.
+const MAX_BODY_LEN: usize = 64 * 1024 * 1024;
+
+/// An HTTP/1.1 request, reduced to what the node endpoints look at.
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// An HTTP/1.1 response, reduced to what the client looks at.
+pub(crate) struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// An `http://host:port/path` URL. There is no TLS support.
+#[derive(Clone, Debug)]
+pub(crate) struct Url {
+    /// `host:port`, as passed to `TcpStream::connect`.
+    pub authority: String,
+    pub path: String,
+}
+
+impl FromStr for Url {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let rest = s.strip_prefix("http://").ok_or_else(|| format!("expected an http:// URL, found `{}`", s))?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() {
+            return Err(format!("`{}` has no host", s));
+        }
+        // Default to port 80 like any other client would.
+        let authority = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+        Ok(Self { authority, path: path.to_string() })
+    }
+}
+
+/// Reads one request from `stream`. Only `Content-Length` bodies are supported.
+pub(crate) fn read_request(stream: &TcpStream) -> Result<Request> {
+    parse_request(&mut BufReader::new(stream))
+}
+
+fn parse_request(reader: &mut impl BufRead) -> Result<Request> {
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(Error::new(ErrorKind::Io, format!("Malformed request line `{}`", request_line)));
+    };
+    let body = read_body(reader, false)?;
+    Ok(Request { method: method.to_string(), path: path.to_string(), body })
+}
+
+/// Writes a JSON response and asks the client to close the connection.
+pub(crate) fn write_response(mut stream: &TcpStream, status: u16, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    )
+    .or_fail(ErrorKind::Io, || "writing the response".into())?;
+    stream.flush().or_fail(ErrorKind::Io, || "writing the response".into())?;
+    Ok(())
+}
+
+/// Sends `method url` with an optional JSON body on a fresh connection and reads the whole response.
+pub(crate) fn send(url: &Url, method: &str, body: Option<&str>) -> Result<Response> {
+    let mut stream = TcpStream::connect(&url.authority).or_fail(ErrorKind::Io, || format!("connecting to {}", url.authority))?;
+    let body = body.unwrap_or("");
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        url.path,
+        url.authority,
+        body.len(),
+        body
+    )
+    .or_fail(ErrorKind::Io, || format!("sending the request to {}", url.authority))?;
+    stream.flush().or_fail(ErrorKind::Io, || format!("sending the request to {}", url.authority))?;
+
+    let mut reader = BufReader::new(&stream);
+    let status_line = read_line(&mut reader)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| Error::new(ErrorKind::Io, format!("Malformed status line `{}` from {}", status_line, url.authority)))?;
+    let body = read_body(&mut reader, true)?;
+    Ok(Response { status, body: String::from_utf8_lossy(&body).into_owned() })
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).or_fail(ErrorKind::Io, || "reading a line".into())? == 0 {
+        return Err(Error::new(ErrorKind::Io, "Connection closed before the end of the headers"));
+    }
+    Ok(line.trim_end().to_string())
+}
+
+/// Skips the headers and reads the body they announce. Without a `Content-Length`, a response body runs to the end
+/// of the connection and a request has none.
+fn read_body(reader: &mut impl BufRead, read_to_end: bool) -> Result<Vec<u8>> {
+    let mut content_length = None;
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(Error::new(ErrorKind::Io, format!("Malformed header `{}`", line)));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = Some(value.parse::<usize>().or_fail(ErrorKind::Io, || format!("parsing Content-Length `{}`", value))?);
+        } else if name.eq_ignore_ascii_case("transfer-encoding") && !value.eq_ignore_ascii_case("identity") {
+            return Err(Error::new(ErrorKind::Io, format!("Unsupported Transfer-Encoding `{}`", value)));
+        }
+    }
+
+    let mut body = Vec::new();
+    match content_length {
+        Some(length) if length > MAX_BODY_LEN => return Err(Error::new(ErrorKind::Io, format!("Body of {} bytes is too large", length))),
+        Some(length) => {
+            body.resize(length, 0);
+            reader.read_exact(&mut body).or_fail(ErrorKind::Io, || format!("reading a body of {} bytes", length))?;
+        }
+        None if read_to_end => {
+            reader.take(MAX_BODY_LEN as u64).read_to_end(&mut body).or_fail(ErrorKind::Io, || "reading the body".into())?;
+        }
+        None => {}
+    }
+    Ok(body)
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(request: &str) -> Result<Request> {
+        parse_request(&mut request.as_bytes())
+    }
+
+    #[test]
+    fn parses_a_well_formed_request() {
+        let request = parse("POST /testnet3/transaction/broadcast HTTP/1.1\r\nHost: x\r\nContent-Length: 4\r\n\r\n{}\r\n").unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/testnet3/transaction/broadcast");
+        assert_eq!(request.body, b"{}\r\n");
+    }
+
+    #[test]
+    fn a_request_without_content_length_has_no_body() {
+        let request = parse("GET /testnet3/latest/height HTTP/1.1\r\nHost: x\r\n\r\nignored").unwrap();
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn rejects_an_oversized_content_length() {
+        let request = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_LEN + 1);
+        assert!(parse(&request).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        assert!(parse("GET\r\n\r\n").is_err());
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: four\r\n\r\n").is_err());
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").is_err());
+        assert!(parse("GET / HTTP/1.1\r\nno colon\r\n\r\n").is_err());
+        assert!(parse("GET / HTTP/1.1\r\nHost: x\r\n").is_err());
+    }
+
+    #[test]
+    fn parses_urls() {
+        let url = "http://127.0.0.1:3030/testnet3/transaction/broadcast".parse::<Url>().unwrap();
+        assert_eq!(url.authority, "127.0.0.1:3030");
+        assert_eq!(url.path, "/testnet3/transaction/broadcast");
+        assert_eq!("http://example.com".parse::<Url>().unwrap().authority, "example.com:80");
+        assert!("https://example.com".parse::<Url>().is_err());
+    }
+}
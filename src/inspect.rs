@@ -0,0 +1,159 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use serde_json::{json, Value as Json};
+use snarkvm::prelude::block::{Block, Input, Output, Transaction, Transition};
+use snarkvm::prelude::{FromBytes, Network, ToBytes};
+use std::fs;
+use std::path::Path;
+
+/// Prints a summary of the `transaction_N` or `block_*` file at `path`, as indented text or, with `as_json`, as JSON.
+pub(crate) fn inspect_file<N: Network>(path: &str, as_json: bool) -> Result<()> {
+    let bytes = fs::read(path).or_fail(ErrorKind::Fixture, || format!("reading {}", path))?;
+    let name = Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+    let summary = if name.starts_with("block_") {
+        let block = Block::<N>::read_le(&bytes[..]).or_fail(ErrorKind::Fixture, || format!("decoding the block in {}", path))?;
+        let transactions = block.transactions().iter().map(|confirmed| transaction_summary(confirmed.transaction())).collect::<Result<Vec<_>>>()?;
+        json!({
+            "file": path,
+            "block_hash": block.hash().to_string(),
+            "height": block.height(),
+            "size": bytes.len(),
+            "accepted": block.transactions().num_accepted(),
+            "rejected": block.transactions().num_rejected(),
+            "aborted": block.aborted_transaction_ids().len(),
+            "transactions": transactions,
+        })
+    } else if name.starts_with("transaction_") {
+        let transaction = Transaction::<N>::read_le(&bytes[..]).or_fail(ErrorKind::Fixture, || format!("decoding the transaction in {}", path))?;
+        let mut summary = transaction_summary(&transaction)?;
+        summary["file"] = json!(path);
+        summary
+    } else {
+        return Err(Error::new(ErrorKind::Config, format!("Don't know how to inspect {}; expected a transaction_N or block_* file", path)));
+    };
+
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&summary).or_fail(ErrorKind::Io, || "formatting the summary".into())?);
+    } else {
+        print_human(&summary, 0);
+    }
+    Ok(())
+}
+
+/// Summarizes a transaction: its ID and type, every transition, the fee and the sizes of its proofs and bytes.
+pub(crate) fn transaction_summary<N: Network>(transaction: &Transaction<N>) -> Result<Json> {
+    let kind = match transaction {
+        Transaction::Deploy(..) => "deploy",
+        Transaction::Execute(..) => "execute",
+        Transaction::Fee(..) => "fee",
+    };
+    let size = transaction.to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing {}", transaction.id()))?.len();
+    let proof_size = match transaction.execution().and_then(|execution| execution.proof()) {
+        Some(proof) => Some(proof.to_bytes_le().or_fail(ErrorKind::Fixture, || "serializing the execution proof".into())?.len()),
+        None => None,
+    };
+    let fee = match transaction.fee_transition() {
+        Some(fee) => json!({
+            "amount": fee.amount().map(|amount| *amount).ok(),
+            "payer": fee.payer().map(|payer| payer.to_string()),
+            "public": fee.is_fee_public(),
+            "proof_size": fee.proof().and_then(|proof| proof.to_bytes_le().ok()).map(|bytes| bytes.len()),
+        }),
+        None => Json::Null,
+    };
+    let transitions = transaction.transitions().map(transition_summary).collect::<Vec<_>>();
+
+    Ok(json!({
+        "id": transaction.id().to_string(),
+        "type": kind,
+        "size": size,
+        "proof_size": proof_size,
+        "fee": fee,
+        "transitions": transitions,
+    }))
+}
+
+fn transition_summary<N: Network>(transition: &Transition<N>) -> Json {
+    let inputs = transition.inputs().iter().map(input_kind).collect::<Vec<_>>();
+    let outputs = transition.outputs().iter().map(output_kind).collect::<Vec<_>>();
+    // The arguments each future hands to its finalize, including those of the futures it awaits.
+    let futures = transition
+        .outputs()
+        .iter()
+        .filter_map(|output| match output {
+            Output::Future(_, Some(future)) => Some(future.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "id": transition.id().to_string(),
+        "program": transition.program_id().to_string(),
+        "function": transition.function_name().to_string(),
+        "inputs": inputs,
+        "outputs": outputs,
+        "futures": futures,
+    })
+}
+
+fn input_kind<N: Network>(input: &Input<N>) -> &'static str {
+    match input {
+        Input::Constant(..) => "constant",
+        Input::Public(..) => "public",
+        Input::Private(..) => "private",
+        Input::Record(..) => "record",
+        Input::ExternalRecord(..) => "external_record",
+    }
+}
+
+fn output_kind<N: Network>(output: &Output<N>) -> &'static str {
+    match output {
+        Output::Constant(..) => "constant",
+        Output::Public(..) => "public",
+        Output::Private(..) => "private",
+        Output::Record(..) => "record",
+        Output::ExternalRecord(..) => "external_record",
+        Output::Future(..) => "future",
+    }
+}
+
+/// Prints a summary as indented `key: value` lines, one list entry per block.
+fn print_human(value: &Json, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Json::Object(fields) => {
+            for (key, field) in fields {
+                match field {
+                    Json::Object(_) | Json::Array(_) if !is_flat(field) => {
+                        println!("{}{}:", pad, key);
+                        print_human(field, indent + 1);
+                    }
+                    _ => println!("{}{}: {}", pad, key, flat(field)),
+                }
+            }
+        }
+        Json::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                println!("{}[{}]", pad, i);
+                print_human(item, indent + 1);
+            }
+        }
+        _ => println!("{}{}", pad, flat(value)),
+    }
+}
+
+fn is_flat(value: &Json) -> bool {
+    match value {
+        Json::Array(items) => items.iter().all(|item| !item.is_object() && !item.is_array()) && items.len() <= 8,
+        Json::Object(_) => false,
+        _ => true,
+    }
+}
+
+fn flat(value: &Json) -> String {
+    match value {
+        Json::String(string) => string.clone(),
+        Json::Null => "-".to_string(),
+        Json::Array(items) => items.iter().map(flat).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
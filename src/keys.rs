@@ -0,0 +1,189 @@
+use crate::cli::Args;
+use crate::error::{ErrorKind, OrFail, Result};
+use crate::workload::Workload;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::{FromBytes, Identifier, Network, PrivateKey, Program, ProgramID, ProvingKey, TestRng, ToBytes, VerifyingKey};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Whether to synthesize a workload's keys before a mode's timer starts, and where to keep them between runs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WarmUpConfig {
+    pub enabled: bool,
+    /// Keys are loaded from and saved to `<cache_dir>/<program>/`, next to the program source they were made for.
+    pub cache_dir: Option<String>,
+}
+
+impl WarmUpConfig {
+    /// Reads `--no-warm-up` and `--key-cache <dir>`.
+    pub(crate) fn from_args(args: &Args) -> Result<Self> {
+        Ok(Self { enabled: !args.flag("no-warm-up")?, cache_dir: args.get_str("key-cache").map(str::to_string) })
+    }
+}
+
+/// The proving and verifying key of one function.
+struct FunctionKeys<N: Network> {
+    program_id: ProgramID<N>,
+    function_name: Identifier<N>,
+    proving_key: ProvingKey<N>,
+    verifying_key: VerifyingKey<N>,
+}
+
+/// The keys of every function a workload executes, so they can be installed into ledgers that run it.
+pub(crate) struct WorkloadKeys<N: Network>(Vec<FunctionKeys<N>>);
+
+impl<N: Network> WorkloadKeys<N> {
+    /// Inserts the keys into `ledger`'s process, so its first execution of the workload skips key synthesis.
+    pub(crate) fn install<C: ConsensusStorage<N>>(&self, ledger: &Ledger<N, C>) -> Result<()> {
+        let process = ledger.vm().process();
+        let process = process.read();
+        for keys in &self.0 {
+            let describe = || format!("installing the keys of {}/{}", keys.program_id, keys.function_name);
+            process.insert_proving_key(&keys.program_id, &keys.function_name, keys.proving_key.clone()).or_fail(ErrorKind::Ledger, describe)?;
+            process.insert_verifying_key(&keys.program_id, &keys.function_name, keys.verifying_key.clone()).or_fail(ErrorKind::Ledger, describe)?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes sure `ledger` has the keys of every function `workload` executes: installs any cached in
+/// `config.cache_dir`, executes the workload once to synthesize the rest, and caches those. Prints what the warm-up
+/// cost. Returns `None` if the warm-up is disabled.
+pub(crate) fn warm_up<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    workload: &Workload,
+    config: &WarmUpConfig,
+) -> Result<Option<WorkloadKeys<N>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let start = Instant::now();
+    let programs = program_tree(ledger, &workload.program_id)?;
+
+    let mut loaded = 0;
+    if let Some(cache_dir) = &config.cache_dir {
+        for program in &programs {
+            let cached = load_keys::<N>(cache_dir, program)?;
+            loaded += cached.0.len();
+            cached.install(ledger)?;
+        }
+    }
+
+    // The first execution synthesizes the keys of every function it calls. Skip it only if every function is cached.
+    let loaded_all = loaded == programs.iter().map(|program| program.functions().len()).sum::<usize>();
+    if !loaded_all {
+        let r = &mut TestRng::default();
+        let inputs = workload.sample_inputs::<N>(r)?;
+        ledger
+            .vm()
+            .execute(private_key, (workload.program_id.as_str(), workload.function_name.as_str()), inputs.into_iter(), None, 0, None, r)
+            .or_fail(ErrorKind::Execution, || format!("warming up {}/{}", workload.program_id, workload.function_name))?;
+    }
+    let keys = collect_keys(ledger, &programs)?;
+
+    if let Some(cache_dir) = &config.cache_dir {
+        if !loaded_all {
+            save_keys(cache_dir, &programs, &keys)?;
+        }
+    }
+    println!(
+        "Key warm-up for {}/{}: {} keys, {} loaded from the cache, took {:?}",
+        workload.program_id,
+        workload.function_name,
+        keys.0.len(),
+        loaded,
+        start.elapsed()
+    );
+    Ok(Some(keys))
+}
+
+/// `program_id` and every program it imports, directly or not, except `credits.aleo`, whose keys ship with snarkVM.
+fn program_tree<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, program_id: &str) -> Result<Vec<Program<N>>> {
+    let credits = ProgramID::<N>::from_str("credits.aleo").or_fail(ErrorKind::Config, || "parsing credits.aleo".into())?;
+    let root = ProgramID::<N>::from_str(program_id).or_fail(ErrorKind::Config, || format!("parsing {}", program_id))?;
+    let process = ledger.vm().process();
+    let process = process.read();
+
+    let mut programs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![root];
+    while let Some(program_id) = pending.pop() {
+        if program_id == credits || !seen.insert(program_id) {
+            continue;
+        }
+        let program = process.get_program(program_id).or_fail(ErrorKind::Fixture, || format!("finding {} on the ledger", program_id))?.clone();
+        pending.extend(program.imports().keys().copied());
+        programs.push(program);
+    }
+    Ok(programs)
+}
+
+/// The keys `ledger` has for the functions of `programs`.
+fn collect_keys<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, programs: &[Program<N>]) -> Result<WorkloadKeys<N>> {
+    let process = ledger.vm().process();
+    let process = process.read();
+    let mut keys = Vec::new();
+    for program in programs {
+        let program_id = *program.id();
+        let stack = process.get_stack(program_id).or_fail(ErrorKind::Ledger, || format!("finding the stack of {}", program_id))?;
+        for function_name in program.functions().keys().copied() {
+            if !stack.contains_proving_key(&function_name) {
+                continue;
+            }
+            let describe = || format!("reading the keys of {}/{}", program_id, function_name);
+            let proving_key = process.get_proving_key(program_id, function_name).or_fail(ErrorKind::Ledger, describe)?;
+            let verifying_key = process.get_verifying_key(program_id, function_name).or_fail(ErrorKind::Ledger, describe)?;
+            keys.push(FunctionKeys { program_id, function_name, proving_key, verifying_key });
+        }
+    }
+    Ok(WorkloadKeys(keys))
+}
+
+/// The keys cached for `program`, or none if the cache holds keys for a different version of it.
+fn load_keys<N: Network>(cache_dir: &str, program: &Program<N>) -> Result<WorkloadKeys<N>> {
+    let program_dir = format!("{}/{}", cache_dir, program.id());
+    let source_path = format!("{}/program.aleo", program_dir);
+    if !Path::new(&source_path).exists() {
+        return Ok(WorkloadKeys(Vec::new()));
+    }
+    if fs::read_to_string(&source_path).or_fail(ErrorKind::Fixture, || format!("reading {}", source_path))? != program.to_string() {
+        return Ok(WorkloadKeys(Vec::new()));
+    }
+
+    let mut keys = Vec::new();
+    for function_name in program.functions().keys().copied() {
+        let (prover_path, verifier_path) = (format!("{}/{}.prover", program_dir, function_name), format!("{}/{}.verifier", program_dir, function_name));
+        if !Path::new(&prover_path).exists() || !Path::new(&verifier_path).exists() {
+            continue;
+        }
+        let proving_key = ProvingKey::<N>::from_bytes_le(&fs::read(&prover_path).or_fail(ErrorKind::Fixture, || format!("reading {}", prover_path))?)
+            .or_fail(ErrorKind::Fixture, || format!("decoding {}", prover_path))?;
+        let verifying_key = VerifyingKey::<N>::from_bytes_le(&fs::read(&verifier_path).or_fail(ErrorKind::Fixture, || format!("reading {}", verifier_path))?)
+            .or_fail(ErrorKind::Fixture, || format!("decoding {}", verifier_path))?;
+        keys.push(FunctionKeys { program_id: *program.id(), function_name, proving_key, verifying_key });
+    }
+    Ok(WorkloadKeys(keys))
+}
+
+/// Caches `keys` along with the source of the programs they belong to.
+fn save_keys<N: Network>(cache_dir: &str, programs: &[Program<N>], keys: &WorkloadKeys<N>) -> Result<()> {
+    for program in programs {
+        let program_dir = format!("{}/{}", cache_dir, program.id());
+        fs::create_dir_all(&program_dir).or_fail(ErrorKind::Io, || format!("creating {}", program_dir))?;
+        fs::write(format!("{}/program.aleo", program_dir), program.to_string()).or_fail(ErrorKind::Io, || format!("writing {}/program.aleo", program_dir))?;
+    }
+    for keys in &keys.0 {
+        let program_dir = format!("{}/{}", cache_dir, keys.program_id);
+        let describe = || format!("serializing the keys of {}/{}", keys.program_id, keys.function_name);
+        let (prover_path, verifier_path) = (format!("{}/{}.prover", program_dir, keys.function_name), format!("{}/{}.verifier", program_dir, keys.function_name));
+        fs::write(&prover_path, keys.proving_key.to_bytes_le().or_fail(ErrorKind::Io, describe)?).or_fail(ErrorKind::Io, || format!("writing {}", prover_path))?;
+        fs::write(&verifier_path, keys.verifying_key.to_bytes_le().or_fail(ErrorKind::Io, describe)?)
+            .or_fail(ErrorKind::Io, || format!("writing {}", verifier_path))?;
+    }
+    Ok(())
+}
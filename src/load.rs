@@ -0,0 +1,178 @@
+use crate::error::{join_scoped_worker, Error, ErrorKind, OrFail, Result};
+use crate::keys::{self, WarmUpConfig};
+use crate::mempool::Mempool;
+use crate::stats::Distribution;
+use crate::test_helpers::*;
+use crate::workload::Workload;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::prelude::{Network, Rng, TestRng};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Settings of a `load` run.
+#[derive(Clone, Debug)]
+pub(crate) struct LoadConfig {
+    /// Target transactions per second.
+    pub rate: f64,
+    pub duration: Duration,
+    /// How often the block producer builds a block from the mempool.
+    pub block_interval: Duration,
+    /// The most transactions the block producer takes from the mempool per block.
+    pub max_block_txs: usize,
+    /// The number of threads creating transactions.
+    pub workers: usize,
+    /// Each created transaction pays a random priority fee up to this many microcredits.
+    pub max_priority_fee: u64,
+    /// Emit the cached `transaction_N` fixtures instead of executing the workload.
+    pub from_corpus: bool,
+}
+
+/// Emits transactions at `config.rate` for `config.duration` into a `Mempool`, while a block producer builds and
+/// advances a block from the mempool's highest paying transactions every `config.block_interval`. Reports the mempool
+/// depth, the latency from creation to inclusion and whether block production kept up.
+pub(crate) fn run_load<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    workload: &Workload,
+    config: &LoadConfig,
+    warm_up: &WarmUpConfig,
+    dev: Option<u16>,
+) -> Result<()> {
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let corpus = match config.from_corpus {
+        true => list_transactions("./transactions")?.into_iter().map(|(_, path)| read_transaction::<N>(&path)).collect::<Result<Vec<_>>>()?,
+        false => Vec::new(),
+    };
+    if config.from_corpus && corpus.is_empty() {
+        return Err(Error::new(ErrorKind::Fixture, "No transaction_N fixtures to emit"));
+    }
+    if !config.from_corpus {
+        keys::warm_up(&ledger, &private_key, workload, warm_up)?;
+    }
+
+    let mempool = Mempool::<N>::new();
+    let next_index = AtomicUsize::new(0);
+    let emitting_done = AtomicBool::new(false);
+    let start = Instant::now();
+    let deadline = start + config.duration;
+    println!("Emitting {} tx/s for {:?} with {} workers, a block every {:?}", config.rate, config.duration, config.workers, config.block_interval);
+
+    let (emitted, lags, report) = thread::scope(|scope| -> Result<_> {
+        // Transaction creators: the `i`-th transaction is due at `start + i / rate`.
+        let workers = (0..config.workers)
+            .map(|worker| {
+                let (ledger, mempool, next_index, corpus) = (&ledger, &mempool, &next_index, &corpus);
+                thread::Builder::new().name(format!("load-{}", worker)).spawn_scoped(scope, move || -> Result<Vec<Duration>> {
+                    let r = &mut TestRng::default();
+                    let mut lags = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::SeqCst);
+                        let due = start + Duration::from_secs_f64(index as f64 / config.rate);
+                        // Each cached transaction can only be included once.
+                        if due >= deadline || (config.from_corpus && index >= corpus.len()) {
+                            return Ok(lags);
+                        }
+                        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                            thread::sleep(wait);
+                        }
+                        // Latency runs from here, so it includes creating the transaction.
+                        let created = Instant::now();
+                        let transaction = if config.from_corpus {
+                            corpus[index].clone()
+                        } else {
+                            let inputs = workload.sample_inputs::<N>(r)?;
+                            let priority_fee = r.gen_range(0..=config.max_priority_fee);
+                            ledger
+                                .vm()
+                                .execute(&private_key, (workload.program_id.as_str(), workload.function_name.as_str()), inputs.into_iter(), None, priority_fee, None, r)
+                                .or_fail(ErrorKind::Execution, || format!("creating load transaction {}", index))?
+                        };
+                        lags.push(Instant::now().saturating_duration_since(due));
+                        // Duplicates are counted by the mempool.
+                        let _ = mempool.submit(ledger, transaction, created);
+                    }
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .or_fail(ErrorKind::Thread, || "spawning the load workers".into())?;
+
+        // Block producer: runs until the emitters are done and the mempool is drained.
+        let producer = {
+            let (ledger, mempool, emitting_done) = (&ledger, &mempool, &emitting_done);
+            thread::Builder::new().name("block-producer".to_string()).spawn_scoped(scope, move || -> Result<ProducerReport> {
+                let rng = &mut TestRng::default();
+                let mut report = ProducerReport::default();
+                loop {
+                    thread::sleep(config.block_interval);
+                    report.depths.push(mempool.len() as u64);
+                    let batch = mempool.take_batch(config.max_block_txs);
+                    if batch.is_empty() {
+                        if emitting_done.load(Ordering::SeqCst) {
+                            return Ok(report);
+                        }
+                        continue;
+                    }
+
+                    let (created, transactions): (Vec<_>, Vec<_>) = batch.into_iter().map(|pending| (pending.created, pending.transaction)).unzip();
+                    let block_start = Instant::now();
+                    let block = ledger
+                        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transactions, rng)
+                        .or_fail(ErrorKind::Ledger, || format!("preparing load block {}", report.blocks))?;
+                    ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || format!("checking load block {}", report.blocks))?;
+                    ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to load block {}", report.blocks))?;
+                    let included = Instant::now();
+
+                    report.blocks += 1;
+                    report.block_times.push(included.duration_since(block_start).as_millis() as u64);
+                    report.included += block.transactions().num_accepted();
+                    report.latencies.extend(created.iter().map(|created| included.duration_since(*created).as_millis() as u64));
+                }
+            })
+            .or_fail(ErrorKind::Thread, || "spawning the block producer".into())?
+        };
+
+        // Stop the producer once every worker is done, even if one failed, so the scope can end.
+        let worker_results = workers.into_iter().map(join_scoped_worker).collect::<Vec<_>>();
+        emitting_done.store(true, Ordering::SeqCst);
+        let report = join_scoped_worker(producer)?;
+
+        let mut emitted = 0;
+        let mut lags = Vec::new();
+        for worker_lags in worker_results {
+            let worker_lags = worker_lags?;
+            emitted += worker_lags.len();
+            lags.extend(worker_lags.into_iter().map(|lag| lag.as_millis() as u64));
+        }
+        Ok((emitted, lags, report))
+    })?;
+
+    let elapsed = start.elapsed();
+    println!("Emitted {} transactions ({:.2} tx/s), included {} in {} blocks over {:?}", emitted, emitted as f64 / config.duration.as_secs_f64(), report.included, report.blocks, elapsed);
+    let lags = Distribution::new(lags);
+    lags.print("Emission lag (ms)");
+    Distribution::new(report.depths).print("Mempool depth");
+    println!("Duplicate submissions dropped: {}", mempool.duplicates());
+    Distribution::new(report.block_times).print("Block time (ms)");
+    Distribution::new(report.latencies).print("Inclusion latency (ms)");
+
+    // Emitters keep up if transactions were created roughly on schedule; the producer keeps up if it drained the
+    // mempool within two block intervals of the emitters finishing: one to include the last batch, one to find the
+    // mempool empty.
+    let emitters_kept_up = lags.percentile(90) as u128 <= config.block_interval.as_millis();
+    let producer_kept_up = elapsed <= config.duration + config.block_interval * 2;
+    println!("Emitters kept up: {}", if emitters_kept_up { "yes" } else { "no" });
+    println!("Block producer kept up: {}", if producer_kept_up { "yes" } else { "no" });
+    Ok(())
+}
+
+#[derive(Default)]
+struct ProducerReport {
+    blocks: usize,
+    included: usize,
+    depths: Vec<u64>,
+    block_times: Vec<u64>,
+    latencies: Vec<u64>,
+}
@@ -1,17 +1,47 @@
+mod accounts;
+mod assertions;
+mod capacity;
+mod cli;
+mod client;
+mod conflicts;
+mod deployments;
+mod error;
+mod estimate;
+mod failures;
+mod fees;
+mod fuzz;
+mod http;
+mod inspect;
+mod keys;
+mod load;
+mod mempool;
+mod mix;
+mod node;
+mod preload;
+mod probe;
+mod programs;
+mod records;
+mod roundtrip;
+mod snapshot;
+mod stats;
 mod test_helpers;
+mod workload;
 
 use std::fs::File;
-use std::{fs, io, thread};
-use std::io::Read;
+use std::sync::Arc;
+use std::{fs, thread};
 use std::path::Path;
 use std::str::FromStr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use error::{catch_panic, join_worker, Context, Error, ErrorKind, OrFail, Result};
 use test_helpers::*;
 
 use snarkvm;
-use snarkvm::prelude::{FromBytes, PrivateKey, TestRng, ToBytes, Value};
+use snarkvm::prelude::{Network, PrivateKey, Rng, TestRng, ToBytes, Value};
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
 use snarkvm::ledger::Ledger;
-use snarkvm::prelude::block::{Block, Transaction};
+use snarkvm::prelude::block::Transaction;
 use snarkvm::prelude::Program;
 
 use std::io::Write;
@@ -20,33 +50,217 @@ use regex::Regex;
 
 const VERIFY_TX_NUM: usize = 169;
 const CREATE_TX_NUM: usize = 40;
+const TRANSFER_TX_NUM: usize = 32;
+const RECORD_TX_NUM: usize = 32;
+const FEE_TX_NUM: usize = 4;
+/// Failed `parallel-spam` jobs tolerated by default, about a tenth of `CREATE_TX_NUM`, so one flaky job doesn't
+/// throw away the rest of the run.
+const MAX_FAILURES: usize = 4;
+
+/// Runs a mode that is generic over the network `N` and the consensus storage on the backend chosen with `--backend`.
+/// The storage dev ID is passed as the last argument, after `$args`.
+macro_rules! with_backend {
+    ($backend:expr, $($mode:ident)::+($($args:expr),*)) => {
+        match $backend {
+            Backend::Memory => $($mode)::+::<N, ConsensusMemory<N>>($($args,)* None),
+            #[cfg(feature = "rocks")]
+            Backend::Rocks { dev } => $($mode)::+::<N, RocksConsensus<N>>($($args,)* Some(dev)),
+        }
+    };
+}
 
 fn main() {
-    parallel_spam().expect("Failed to spam finalize ops");
-    //verify_finalize_ops().expect("Failed to spam finalize ops");
+    let args = cli::Args::parse();
+    let result = match args.get_str("network").unwrap_or("testnet3") {
+        "testnet3" => run::<CurrentNetwork>(&args),
+        network => Err(Error::new(
+            ErrorKind::Config,
+            format!("Unsupported network `{}`; this snarkVM checkout only provides `testnet3`", network),
+        )),
+    };
+    if let Err(error) = result {
+        error.print_summary(&args.mode);
+        std::process::exit(error.kind.exit_code());
+    }
+}
+
+fn run<N: Network>(args: &cli::Args) -> Result<()> {
+    let backend = backend_from_args::<N>(args)?;
+    // Start from a saved ledger instead of replaying the cached blocks.
+    let snapshot_dir = args.get_str("snapshot");
+    // Synthesize the workload's keys before the timer starts, unless `--no-warm-up` is given.
+    let warm_up = keys::WarmUpConfig::from_args(args)?;
+    match args.mode.as_str() {
+        "parallel-spam" => {
+            // Failed jobs are logged and skipped until more than `--max-failures` of them have failed.
+            let failure_log = failures::FailureLog::open(args.get_str("failure-log").unwrap_or("./failures.log"), args.get("max-failures", MAX_FAILURES)?)?;
+            parallel_spam::<N>(Arc::new(failure_log), &warm_up)
+        }
+        "reproduce-job" => match args.get_str("seed") {
+            Some(_) => reproduce_job::<N>(args.get("seed", 0u64)?),
+            None => Err(Error::new(ErrorKind::Config, "reproduce-job needs the --seed of a job from the failure log")),
+        },
+        "verify" => with_backend!(backend, verify_finalize_ops(snapshot_dir, &warm_up)),
+        "spam-verify" => with_backend!(backend, spam_finalize_ops_parallel(snapshot_dir, &warm_up)),
+        "create-blocks" => create_blocks::<N>(fees::FeeConfig::from_args(args)?),
+        "create-mix-block" => mix::create_mix_block::<N>(fees::FeeConfig::from_args(args)?),
+        "verify-mix" => with_backend!(backend, mix::verify_finalize_mix(snapshot_dir)),
+        "snapshot" => with_backend!(
+            backend,
+            snapshot::create_snapshot(snapshot_dir.unwrap_or("./snapshots/latest"), args.get("extra-blocks", 0u32)?)
+        ),
+        "preload" => {
+            let txs_per_block = args.get("txs-per-block", 64)?;
+            if txs_per_block == 0 {
+                return Err(Error::new(ErrorKind::Config, "--txs-per-block must be positive"));
+            }
+            preload::preload_mapping::<N>(mix::MIX_PROGRAM_ID, args.get("entries", 16_384)?, txs_per_block)
+        }
+        "fund-pool" => accounts::fund_pool::<N>(
+            args.get("pool-size", 16)?,
+            args.get("public-amount", 1_000_000_000)?,
+            args.get("private-amount", 100_000_000)?,
+            args.get("records-per-account", 2)?,
+        ),
+        "spam-transfers" => with_backend!(backend, accounts::spam_transfers(snapshot_dir, args.get("transfers", TRANSFER_TX_NUM)?)),
+        "spam-records" => {
+            let spends = args
+                .get_str("spends")
+                .unwrap_or("transfer_private,split,join")
+                .split(',')
+                .map(|spend| spend.parse().or_fail(ErrorKind::Config, || "parsing --spends".into()))
+                .collect::<Result<Vec<records::RecordSpend>>>()?;
+            with_backend!(backend, records::spam_records(snapshot_dir, &spends, args.get("max-txs", RECORD_TX_NUM)?))
+        }
+        "conflicts" => with_backend!(backend, conflicts::check_conflicts(snapshot_dir)),
+        "fuzz" => fuzz::fuzz_transactions::<N>(
+            args.get("iterations", 1_000)?,
+            args.get("seed", 6404264900108107703)?,
+            args.get_str("reproducers").unwrap_or("./fuzz_reproducers"),
+        ),
+        "roundtrip" => roundtrip::check_corpus_roundtrips::<N>("./transactions", args.flag("update-ids")?),
+        "inspect" => match args.positional.first() {
+            Some(path) => inspect::inspect_file::<N>(path, args.flag("json")?),
+            None => Err(Error::new(ErrorKind::Config, "inspect needs the path of a transaction_N or block_* file")),
+        },
+        "stats" => stats::corpus_stats::<N>("./transactions"),
+        "spam-fees" => {
+            let fee_config = fees::FeeConfig::from_args(args)?;
+            with_backend!(backend, fees::spam_fees(snapshot_dir, fee_config, args.get("txs", FEE_TX_NUM)?))
+        }
+        "estimate" => match (args.get_str("deploy"), args.positional.split_first()) {
+            // `--deploy <file.aleo>` estimates a deployment instead.
+            (Some(path), _) => estimate::estimate_deployment::<N>(&fs::read_to_string(path).or_fail(ErrorKind::Config, || format!("reading {}", path))?),
+            (None, Some((locator, inputs))) => estimate::estimate_execution::<N>(locator, inputs, args.flag("check")?),
+            (None, None) => Err(Error::new(ErrorKind::Config, "estimate needs a program/function and its inputs, or --deploy <file.aleo>")),
+        },
+        "capacity" => {
+            let limits = capacity::BlockLimits {
+                max_finalize_writes: args.get("max-block-writes", usize::MAX)?,
+                max_block_bytes: args.get("max-block-bytes", usize::MAX)?,
+            };
+            with_backend!(
+                backend,
+                capacity::find_block_capacity(snapshot_dir, args.get("max-txs", usize::MAX)?, Duration::from_secs(args.get("time-budget-secs", 60)?), &limits)
+            )
+        }
+        "probe" => with_backend!(
+            backend,
+            probe::probe_finalize_limits(snapshot_dir, args.get("max-sets", 256)?, args.get("max-calls", 64)?, args.get("max-block-txs", 64)?)
+        ),
+        "load" => {
+            let config = load::LoadConfig {
+                rate: args.get("rate", 1.0)?,
+                duration: Duration::from_secs(args.get("duration-secs", 60)?),
+                block_interval: Duration::from_millis(args.get("block-interval-ms", 10_000)?),
+                max_block_txs: args.get("max-block-txs", VERIFY_TX_NUM)?,
+                workers: args.get("workers", num_cpus::get())?,
+                max_priority_fee: args.get("max-priority-fee", 0)?,
+                from_corpus: args.flag("from-corpus")?,
+            };
+            if !(config.rate > 0.0) || config.workers == 0 || config.max_block_txs == 0 {
+                return Err(Error::new(ErrorKind::Config, "--rate, --workers and --max-block-txs must be positive"));
+            }
+            with_backend!(backend, load::run_load(snapshot_dir, &workload::outer_most_call(), &config, &warm_up))
+        }
+        "serve" => with_backend!(
+            backend,
+            node::serve(
+                snapshot_dir,
+                args.get_str("address").unwrap_or("127.0.0.1:3030"),
+                Duration::from_millis(args.get("block-interval-ms", 10_000)?),
+                args.get("max-block-txs", VERIFY_TX_NUM)?
+            )
+        ),
+        "submit" => {
+            let endpoint = client::parse_endpoint(args.get_str("endpoint").unwrap_or(client::DEFAULT_ENDPOINT))?;
+            let config = client::ReplayConfig {
+                rate: args.get_str("rate").map(|_| args.get("rate", 0.0)).transpose()?,
+                concurrency: args.get("concurrency", 1)?,
+                responses_path: args.get_str("responses").map(str::to_string),
+            };
+            if config.rate.map_or(false, |rate| !(rate > 0.0)) || config.concurrency == 0 {
+                return Err(Error::new(ErrorKind::Config, "--rate and --concurrency must be positive"));
+            }
+            client::replay_corpus::<N>("./transactions", &endpoint, &config)
+        }
+        "bench-deploy" => {
+            let shapes = args
+                .get_str("shapes")
+                .unwrap_or("1x1,1x16,4x1,4x16,16x1")
+                .split(',')
+                .map(|shape| shape.parse().or_fail(ErrorKind::Config, || "parsing --shapes".into()))
+                .collect::<Result<Vec<deployments::ProgramShape>>>()?;
+            let workers = args.get("workers", num_cpus::get())?;
+            if workers == 0 {
+                return Err(Error::new(ErrorKind::Config, "--workers must be positive"));
+            }
+            deployments::bench_deployments::<N>(&shapes, workers)
+        }
+        mode => Err(Error::new(ErrorKind::Config, format!("Unknown mode `{}`", mode))),
+    }
 }
 
-fn parallel_spam() -> io::Result<()> {
+fn parallel_spam<N: Network>(failure_log: Arc<failures::FailureLog>, warm_up: &keys::WarmUpConfig) -> Result<()> {
     // Compute parallelization logic
     let mut handles = Vec::new();
     let num_cpus = num_cpus::get();
     let work_per_thread = (CREATE_TX_NUM + num_cpus - 1) / num_cpus; // Round up
 
-    // Check if `./transactions` directory exists
+    // Check that existing fixtures are for this network, and tag a new `./transactions` directory with it.
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    if Path::new(transactions_dir).exists() {
+        check_fixture_network::<N>(transactions_dir)?;
+    } else {
+        fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+        tag_fixture_network::<N>(transactions_dir)?;
+    }
     let max_number = find_max_transaction_number(transactions_dir)?;
 
+    // Synthesize the keys once; every thread installs them into its own ledger.
+    let workload_keys = Arc::new(match warm_up.enabled {
+        true => {
+            let rng = &mut TestRng::fixed(6404264900108107703);
+            let crate::test_helpers::TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+            load_spammer_blocks(&ledger, transactions_dir)?;
+            keys::warm_up(&ledger, &private_key, &workload::outer_most_call(), warm_up)?
+        }
+        false => None,
+    });
+
     // Start the timer
     let start = Instant::now();
 
     // Spawn threads to split workload
     for i in 0..num_cpus {
+        let failure_log = failure_log.clone();
+        let workload_keys = workload_keys.clone();
         let handle = thread::Builder::new()
+            .name(format!("creator-{}", i))
             .spawn(move || {
-               parallel_tx_creator(work_per_thread, i)
+               parallel_tx_creator::<N>(work_per_thread, i, &failure_log, (*workload_keys).as_ref())
             })
-            .unwrap(); // Handle potential errors from thread spawning
+            .or_fail(ErrorKind::Thread, || format!("spawning creator-{}", i))?;
         handles.push(handle);
     }
 
@@ -54,7 +268,7 @@ fn parallel_spam() -> io::Result<()> {
 
     // Collect the results from each thread
     for handle in handles {
-        let result = handle.join().unwrap().unwrap();
+        let result = join_worker(handle)?;
 
         results.push(result);
     }
@@ -63,9 +277,9 @@ fn parallel_spam() -> io::Result<()> {
 
     for i in 0..assembled_transaction_list.len() {
         let file_path = format!("{}/transaction_{}", transactions_dir, (i as u32)+ 1 + max_number);
-        let mut file = File::create(&file_path)?;
-        let tx_bytes:Vec<u8> = assembled_transaction_list[i].to_bytes_le().expect("Failed to serialize transaction");
-        file.write(&tx_bytes).expect("Failed to write transaction to file");
+        let mut file = File::create(&file_path).or_fail(ErrorKind::Io, || format!("creating {}", file_path))?;
+        let tx_bytes:Vec<u8> = assembled_transaction_list[i].to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing {}", file_path))?;
+        file.write_all(&tx_bytes).or_fail(ErrorKind::Io, || format!("writing {}", file_path))?;
     }
 
     // Stop the timer
@@ -76,58 +290,39 @@ fn parallel_spam() -> io::Result<()> {
     println!("Time elapsed per transactions is {:?}", duration / (num_cpus * work_per_thread) as u32);
     println!("Num cpus: {}", num_cpus);
     println!("Work per thread: {}", work_per_thread);
+    if failure_log.failures() > 0 {
+        println!("Failed jobs: {} (see {})", failure_log.failures(), failure_log.path());
+    }
 
     Ok(())
 }
-fn parallel_tx_creator(num_jobs: usize, thread_id: usize) -> io::Result<(Vec<Transaction<CurrentNetwork>>)> {
+fn parallel_tx_creator<N: Network>(
+    num_jobs: usize,
+    thread_id: usize,
+    failure_log: &failures::FailureLog,
+    workload_keys: Option<&keys::WorkloadKeys<N>>,
+) -> Result<Vec<Transaction<N>>> {
     // Make sure directory ok
     let transactions_dir = "./transactions";
     let rng = &mut TestRng::fixed(6404264900108107703);
 
     // Initialize the test environment.
-    let crate::test_helpers::TestEnv { ledger, private_key, view_key, .. } = crate::test_helpers::sample_test_env(rng);
-
-    // Read child block from bytes
-    let file_path = format!("{}/block_child", transactions_dir);
-    let mut child_file = File::open(&file_path)?;
-    let child_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut child_file).expect("Failed to read child block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Read parent block from bytes
-    let parent_path = format!("{}/block_parent", transactions_dir);
-    let mut parent_file = File::open(&parent_path)?;
-    let parent_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut parent_file).expect("Failed to read parent block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Read grandfather block from bytes
-    let grandfather_path = format!("{}/block_grandfather", transactions_dir);
-    let mut grandfather_file = File::open(&grandfather_path)?;
-    let grandfather_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut grandfather_file).expect("Failed to read grandfather block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_deploy_transfer_block).unwrap();
+    let crate::test_helpers::TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    load_spammer_blocks(&ledger, transactions_dir)?;
+    if let Some(workload_keys) = workload_keys {
+        workload_keys.install(&ledger)?;
+    }
 
     // Complete threads portion of workload
-    let mut grandfather_execute_transactions: Vec<Transaction<CurrentNetwork>> = Vec::new();
+    let seeds = &mut TestRng::default();
+    let mut grandfather_execute_transactions: Vec<Transaction<N>> = Vec::new();
     for i in 0..num_jobs {
-        let r = &mut TestRng::default();
-        let execute_inputs: Vec<Value<CurrentNetwork>> = Vec::new();
-        let new_tx = ledger.vm().execute(&private_key, ("grandfather_spammer.aleo", "outer_most_call"), execute_inputs.into_iter(), None, 0, None, r)
-            .unwrap();
-        grandfather_execute_transactions.push(new_tx);
+        // Each job gets its own seed, so a failed job can be rerun on its own with `reproduce-job`.
+        let seed: u64 = seeds.gen();
+        match catch_panic(|| create_job_transaction(&ledger, &private_key, seed)) {
+            Ok(new_tx) => grandfather_execute_transactions.push(new_tx),
+            Err(error) => failure_log.record(seed, thread_id, i, &error)?,
+        }
 
         // Print out progress
         println!("------------------------------------------------------------------");
@@ -138,63 +333,66 @@ fn parallel_tx_creator(num_jobs: usize, thread_id: usize) -> io::Result<(Vec<Tra
     Ok(grandfather_execute_transactions)
 }
 
-fn verify_finalize_ops() -> io::Result<()> {
-    // Make sure directory ok
-    let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+/// Creates one `parallel-spam` job's transaction from its seed.
+fn create_job_transaction<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    seed: u64,
+) -> Result<Transaction<N>> {
+    let r = &mut TestRng::fixed(seed);
+    let execute_inputs: Vec<Value<N>> = Vec::new();
+    ledger.vm().execute(private_key, ("grandfather_spammer.aleo", "outer_most_call"), execute_inputs.into_iter(), None, 0, None, r)
+        .or_fail(ErrorKind::Execution, || format!("executing grandfather_spammer.aleo/outer_most_call with seed {}", seed))
+}
 
+/// Reruns the `parallel-spam` job with the given seed, e.g. one taken from the failure log.
+fn reproduce_job<N: Network>(seed: u64) -> Result<()> {
+    let transactions_dir = "./transactions";
     let rng = &mut TestRng::fixed(6404264900108107703);
 
-    // Initialize the test environment.
-    let crate::test_helpers::TestEnv { ledger, private_key, view_key, .. } = crate::test_helpers::sample_test_env(rng);
-
-    // Read child block from bytes
-    let file_path = format!("{}/block_child", transactions_dir);
-    let mut child_file = File::open(&file_path)?;
-    let child_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut child_file).expect("Failed to read child block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&child_deploy_transfer_block).unwrap();
+    // Initialize the same test environment as `parallel_tx_creator`.
+    let crate::test_helpers::TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    load_spammer_blocks(&ledger, transactions_dir)?;
 
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Read parent block from bytes
-    let parent_path = format!("{}/block_parent", transactions_dir);
-    let mut parent_file = File::open(&parent_path)?;
-    let parent_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut parent_file).expect("Failed to read parent block from file");
+    let start = Instant::now();
+    let transaction = catch_panic(|| create_job_transaction(&ledger, &private_key, seed))?;
+    println!("Job with seed {} created transaction {} in {:?}", seed, transaction.id(), start.elapsed());
 
-    // Check that the next block is valid.
-    ledger.check_next_block(&parent_deploy_transfer_block).unwrap();
+    Ok(())
+}
 
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&parent_deploy_transfer_block).unwrap();
+fn verify_finalize_ops<N: Network, C: ConsensusStorage<N>>(snapshot_dir: Option<&str>, warm_up: &keys::WarmUpConfig, dev: Option<u16>) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
-    // Read grandfather block from bytes
-    let grandfather_path = format!("{}/block_grandfather", transactions_dir);
-    let mut grandfather_file = File::open(&grandfather_path)?;
-    let grandfather_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut grandfather_file).expect("Failed to read grandfather block from file");
+    let rng = &mut TestRng::fixed(6404264900108107703);
 
-    // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_deploy_transfer_block).unwrap();
+    // Initialize the test environment.
+    let crate::test_helpers::TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
 
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_deploy_transfer_block).unwrap();
+    // The workload to verify, along with the mapping state it should leave behind.
+    let workload = workload::outer_most_call();
+    keys::warm_up(&ledger, &private_key, &workload, warm_up)?;
 
     // Helper function to assemble grandfather execute transaction
-    fn create_transaction(l: &CurrentLedger, pk: &PrivateKey<CurrentNetwork>) -> Transaction<CurrentNetwork> {
+    fn create_transaction<N: Network, C: ConsensusStorage<N>>(
+        l: &Ledger<N, C>,
+        pk: &PrivateKey<N>,
+        w: &workload::Workload,
+    ) -> Result<Transaction<N>> {
         let r = &mut TestRng::default();
         // Append an `grandfather_spam.aleo/outer_most_call` execute transaction to the list of transactions.
-        let execute_inputs: Vec<Value<CurrentNetwork>> = Vec::new();
-        l.vm().execute(pk, ("grandfather_spammer.aleo", "outer_most_call"), execute_inputs.into_iter(), None, 0, None, r)
-            .unwrap()
+        let execute_inputs = w.sample_inputs::<N>(r)?;
+        l.vm().execute(pk, (w.program_id.as_str(), w.function_name.as_str()), execute_inputs.into_iter(), None, 0, None, r)
+            .or_fail(ErrorKind::Execution, || format!("executing {}/{}", w.program_id, w.function_name))
     }
 
     let start = Instant::now(); // TODO: Need to modify to only include new ones built
 
     // Check if `./transactions` directory exists
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
     // Spawn threads to split workload
     let mut grandfather_execute_transactions = Vec::new();
@@ -202,22 +400,19 @@ fn verify_finalize_ops() -> io::Result<()> {
         let file_path = format!("{}/transaction_{}", transactions_dir, i);
 
         if Path::new(&file_path).exists() {
-            let mut file = File::open(&file_path)?;
-            let mut contents = String::new();
-            // TODO: File is bytes. Store them in variable.
-            let tx = Transaction::<CurrentNetwork>::read_le(&mut file).expect("Failed to read transaction from file");
+            let tx = read_transaction::<N>(&file_path)?;
             grandfather_execute_transactions.push(tx);
         } else {
             // Create transaction
-            let new_tx = create_transaction(&ledger, &private_key);
+            let new_tx = create_transaction(&ledger, &private_key, &workload).context(|| format!("creating {}", file_path))?;
 
             // Append to list of transactions
             grandfather_execute_transactions.push(new_tx.clone());
 
             // Write serialized version to file
-            let mut file = File::create(&file_path)?;
-            let tx_bytes:Vec<u8> = new_tx.to_bytes_le().expect("Failed to serialize transaction");
-            file.write(&tx_bytes).expect("Failed to write transaction to file");
+            let mut file = File::create(&file_path).or_fail(ErrorKind::Io, || format!("creating {}", file_path))?;
+            let tx_bytes:Vec<u8> = new_tx.to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing {}", file_path))?;
+            file.write_all(&tx_bytes).or_fail(ErrorKind::Io, || format!("writing {}", file_path))?;
         }
 
 
@@ -237,13 +432,13 @@ fn verify_finalize_ops() -> io::Result<()> {
     // Construct the next block.
     let grandfather_execute_transfer_block = ledger
         .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], grandfather_execute_transactions, rng)
-        .unwrap();
+        .or_fail(ErrorKind::Ledger, || "preparing the execution block".into())?;
 
     // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_execute_transfer_block).unwrap();
+    ledger.check_next_block(&grandfather_execute_transfer_block).or_fail(ErrorKind::Ledger, || "checking the execution block".into())?;
 
     // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_execute_transfer_block).unwrap();
+    ledger.advance_to_next_block(&grandfather_execute_transfer_block).or_fail(ErrorKind::Ledger, || "advancing to the execution block".into())?;
 
     // Stop the timer
     let duration = start.elapsed();
@@ -252,82 +447,54 @@ fn verify_finalize_ops() -> io::Result<()> {
     println!("Time elapsed is: {:?}", duration);
     println!("Time elapsed per transactions is {:?}", duration / VERIFY_TX_NUM as u32);
 
+    // Check that finalize left the mappings in the expected state.
+    assertions::check_mapping_expectations(&ledger, &workload.expected_mappings)?;
+
     Ok(())
 }
 
-fn spam_finalize_ops_parallel() -> io::Result<()> {
+fn spam_finalize_ops_parallel<N: Network, C: ConsensusStorage<N>>(snapshot_dir: Option<&str>, warm_up: &keys::WarmUpConfig, dev: Option<u16>) -> Result<()> {
     // Make sure directory ok
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
     let rng = &mut TestRng::fixed(6404264900108107703);
 
     // Initialize the test environment.
-    let crate::test_helpers::TestEnv { ledger, private_key, view_key, .. } = crate::test_helpers::sample_test_env(rng);
-
-    // Read child block from bytes
-    let file_path = format!("{}/block_child", transactions_dir);
-    let mut child_file = File::open(&file_path)?;
-    let child_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut child_file).expect("Failed to read child block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Read parent block from bytes
-    let parent_path = format!("{}/block_parent", transactions_dir);
-    let mut parent_file = File::open(&parent_path)?;
-    let parent_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut parent_file).expect("Failed to read parent block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Read grandfather block from bytes
-    let grandfather_path = format!("{}/block_grandfather", transactions_dir);
-    let mut grandfather_file = File::open(&grandfather_path)?;
-    let grandfather_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut grandfather_file).expect("Failed to read grandfather block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_deploy_transfer_block).unwrap();
+    let crate::test_helpers::TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
 
     // Helper function to assemble grandfather execute transaction
-    fn create_transaction(
-        l: &CurrentLedger,
-        pk: &PrivateKey<CurrentNetwork>,
+    fn create_transaction<N: Network, C: ConsensusStorage<N>>(
+        l: &Ledger<N, C>,
+        pk: &PrivateKey<N>,
         r: &mut TestRng,
-    ) -> Transaction<CurrentNetwork> {
+    ) -> Result<Transaction<N>> {
         // Append an `grandfather_spam.aleo/outer_most_call` execute transaction to the list of transactions.
-        let execute_inputs: Vec<Value<CurrentNetwork>> = Vec::new();
+        let execute_inputs: Vec<Value<N>> = Vec::new();
         l.vm().execute(pk, ("grandfather_spammer.aleo", "outer_most_call"), execute_inputs.into_iter(), None, 0, None, r)
-            .unwrap()
+            .or_fail(ErrorKind::Execution, || "executing grandfather_spammer.aleo/outer_most_call".into())
     }
 
     // Helper function to complete threads portion of workload
-    fn complete_threads_portion_of_workload(
-        l: &CurrentLedger,
-        pk: &PrivateKey<CurrentNetwork>,
+    fn complete_threads_portion_of_workload<N: Network, C: ConsensusStorage<N>>(
+        l: &Ledger<N, C>,
+        pk: &PrivateKey<N>,
         num_jobs: usize,
         thread_id: usize,
-    ) -> Vec<Transaction<CurrentNetwork>> {
+    ) -> Result<Vec<Transaction<N>>> {
         let r = &mut TestRng::default();
-        let mut grandfather_execute_transactions: Vec<Transaction<CurrentNetwork>> = Vec::new();
+        let mut grandfather_execute_transactions: Vec<Transaction<N>> = Vec::new();
         for i in 0..num_jobs {
-            grandfather_execute_transactions.push(create_transaction(l, pk, r));
+            grandfather_execute_transactions.push(
+                create_transaction(l, pk, r).context(|| format!("creating transaction {} on thread {}", i, thread_id))?,
+            );
 
             // Print out progress
             println!("------------------------------------------------------------------");
             println!("Thread: {} has completed {}/{} tasks!", thread_id, i + 1, num_jobs);
             println!("------------------------------------------------------------------");
         }
-        grandfather_execute_transactions
+        Ok(grandfather_execute_transactions)
     }
 
     // Compute parallelization logic
@@ -337,9 +504,12 @@ fn spam_finalize_ops_parallel() -> io::Result<()> {
 
     // Check if `./transactions` directory exists
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
     let max_number = find_max_transaction_number(transactions_dir)?;
 
+    // The threads share the ledger, so one warm-up covers all of them.
+    keys::warm_up(&ledger, &private_key, &workload::outer_most_call(), warm_up)?;
+
     // Start the timer
     let start = Instant::now();
 
@@ -353,7 +523,7 @@ fn spam_finalize_ops_parallel() -> io::Result<()> {
             .spawn(move || {
                 complete_threads_portion_of_workload(&ledger_ref, &private_key, work_per_thread, i)
             })
-            .unwrap(); // Handle potential errors from thread spawning
+            .or_fail(ErrorKind::Thread, || format!("spawning worker-{}", i))?;
         handles.push(handle);
     }
 
@@ -361,7 +531,7 @@ fn spam_finalize_ops_parallel() -> io::Result<()> {
 
     // Collect the results from each thread
     for handle in handles {
-        let result = handle.join().unwrap();
+        let result = join_worker(handle)?;
 
         results.push(result);
     }
@@ -370,21 +540,21 @@ fn spam_finalize_ops_parallel() -> io::Result<()> {
 
     for i in 0..assembled_transaction_list.len() {
         let file_path = format!("{}/transaction_{}", transactions_dir, (i as u32)+ 1 + max_number);
-        let mut file = File::create(&file_path)?;
-        let tx_bytes:Vec<u8> = assembled_transaction_list[i].to_bytes_le().expect("Failed to serialize transaction");
-        file.write(&tx_bytes).expect("Failed to write transaction to file");
+        let mut file = File::create(&file_path).or_fail(ErrorKind::Io, || format!("creating {}", file_path))?;
+        let tx_bytes:Vec<u8> = assembled_transaction_list[i].to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing {}", file_path))?;
+        file.write_all(&tx_bytes).or_fail(ErrorKind::Io, || format!("writing {}", file_path))?;
     }
 
     // Construct the next block.
     let grandfather_execute_transfer_block = ledger
         .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], assembled_transaction_list, rng)
-        .unwrap();
+        .or_fail(ErrorKind::Ledger, || "preparing the execution block".into())?;
 
     // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_execute_transfer_block).unwrap();
+    ledger.check_next_block(&grandfather_execute_transfer_block).or_fail(ErrorKind::Ledger, || "checking the execution block".into())?;
 
     // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_execute_transfer_block).unwrap();
+    ledger.advance_to_next_block(&grandfather_execute_transfer_block).or_fail(ErrorKind::Ledger, || "advancing to the execution block".into())?;
 
     // Stop the timer
     let duration = start.elapsed();
@@ -394,199 +564,63 @@ fn spam_finalize_ops_parallel() -> io::Result<()> {
     println!("Time elapsed per transactions is {:?}", duration / (num_cpus * work_per_thread) as u32);
     println!("Num cpus: {}", num_cpus);
     println!("Work per thread: {}", work_per_thread);
+
+    // Check that finalize left the mappings in the expected state.
+    assertions::check_mapping_expectations(&ledger, &workload::outer_most_call().expected_mappings)?;
     Ok(())
 }
 
-fn create_blocks() -> io::Result<()> {
+fn create_blocks<N: Network>(fee_config: fees::FeeConfig) -> Result<()> {
     // Make sure directory ok
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
     let rng = &mut TestRng::fixed(6404264900108107703);
 
     // Initialize the test environment.
-    let crate::test_helpers::TestEnv { ledger, private_key, view_key, .. } = crate::test_helpers::sample_test_env(rng);
-
-    // `child_spammer.aleo` source code
-    let child_program = Program::<CurrentNetwork>::from_str(
-        r"
-program child_spammer.aleo;
-
-mapping map:
-	key as u8.public;
-	value as u8.public;
-
-function spam:
-    async spam into r0;
-    output r0 as child_spammer.aleo/spam.future;
-
-finalize spam:
-    set 0u8 into map[0u8];
-    set 1u8 into map[1u8];
-    set 2u8 into map[2u8];
-    set 3u8 into map[3u8];
-    set 4u8 into map[4u8];
-    set 5u8 into map[5u8];
-    set 6u8 into map[6u8];
-    set 7u8 into map[7u8];
-    set 8u8 into map[8u8];
-    set 9u8 into map[9u8];
-    set 10u8 into map[10u8];
-    set 11u8 into map[11u8];
-    set 12u8 into map[12u8];
-    set 13u8 into map[13u8];
-    set 14u8 into map[14u8];
-    set 15u8 into map[15u8];",
-    )
-        .unwrap();
-
-    // Create transaction deploying `child_spammer.aleo`
-    let child_deploy_transaction = ledger.vm().deploy(&private_key, &child_program, None, 0, None, rng).unwrap();
-
-    // Construct the next block.
-    let child_deploy_transfer_block = ledger
-        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![child_deploy_transaction], rng)
-        .unwrap();
-
-    // Cache child block
-    let file_path = format!("{}/block_child", transactions_dir);
-    let child_block_bytes = child_deploy_transfer_block.to_bytes_le().expect("Can't serialize into child block to bytes");
-    let mut child_file = File::create(&file_path)?;
-    child_file.write(&child_block_bytes).expect("Failed to write child block to file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&child_deploy_transfer_block).unwrap();
-
-    // `parent_spammer.aleo` source code
-    let parent_program = Program::<CurrentNetwork>::from_str(
-        r"
-import child_spammer.aleo;
-program parent_spammer.aleo;
-
-function main:
-    call child_spammer.aleo/spam into r0;
-    call child_spammer.aleo/spam into r1;
-    call child_spammer.aleo/spam into r2;
-    call child_spammer.aleo/spam into r3;
-    call child_spammer.aleo/spam into r4;
-    call child_spammer.aleo/spam into r5;
-    call child_spammer.aleo/spam into r6;
-    call child_spammer.aleo/spam into r7;
-    call child_spammer.aleo/spam into r8;
-    call child_spammer.aleo/spam into r9;
-    call child_spammer.aleo/spam into r10;
-    call child_spammer.aleo/spam into r11;
-    call child_spammer.aleo/spam into r12;
-    call child_spammer.aleo/spam into r13;
-    async main r0 r1 r2 r3 r4 r5 r6 r7 r8 r9 r10 r11 r12 r13 into r14;
-    output r14 as parent_spammer.aleo/main.future;
-
-finalize main:
-    input r0 as child_spammer.aleo/spam.future;
-    input r1 as child_spammer.aleo/spam.future;
-    input r2 as child_spammer.aleo/spam.future;
-    input r3 as child_spammer.aleo/spam.future;
-    input r4 as child_spammer.aleo/spam.future;
-    input r5 as child_spammer.aleo/spam.future;
-    input r6 as child_spammer.aleo/spam.future;
-    input r7 as child_spammer.aleo/spam.future;
-    input r8 as child_spammer.aleo/spam.future;
-    input r9 as child_spammer.aleo/spam.future;
-    input r10 as child_spammer.aleo/spam.future;
-    input r11 as child_spammer.aleo/spam.future;
-    input r12 as child_spammer.aleo/spam.future;
-    input r13 as child_spammer.aleo/spam.future;
-    await r0;
-    await r1;
-    await r2;
-    await r3;
-    await r4;
-    await r5;
-    await r6;
-    await r7;
-    await r8;
-    await r9;
-    await r10;
-    await r11;
-    await r12;
-    await r13;
-",
-    )
-        .unwrap();
-
-    // Create transaction deploying `parent_spammer.aleo`
-    let parent_deploy_transaction = ledger.vm().deploy(&private_key, &parent_program, None, 0, None, rng).unwrap();
-
-    // Construct the next block.
-    let parent_deploy_transfer_block = ledger
-        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![parent_deploy_transaction], rng)
-        .unwrap();
-
-    // Cache parent block
-    let parent_file_path = format!("{}/block_parent", transactions_dir);
-    let parent_block_bytes = parent_deploy_transfer_block.to_bytes_le().expect("Can't serialize into parent block to bytes");
-    let mut parent_file = File::create(&parent_file_path)?;
-    parent_file.write(&parent_block_bytes).expect("Failed to write parent block to file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&parent_deploy_transfer_block).unwrap();
-    // `grandfather_spammer.aleo` source code
-    let grandfather_program = Program::<CurrentNetwork>::from_str(
-        r"
-import child_spammer.aleo;
-import parent_spammer.aleo;
-program grandfather_spammer.aleo;
-
-
-
-function outer_most_call:
-    call parent_spammer.aleo/main into r0;
-    call parent_spammer.aleo/main into r1;
-    async outer_most_call r0 r1 into r2;
-    output r2 as grandfather_spammer.aleo/outer_most_call.future;
-
-finalize outer_most_call:
-    input r0 as parent_spammer.aleo/main.future;
-    input r1 as parent_spammer.aleo/main.future;
-    await r0;
-    await r1;
-",
-    )
-        .unwrap();
-
-    // Create transaction deploying `grandfather_spammer.aleo`
-    let grandfather_deploy_transaction =
-        ledger.vm().deploy(&private_key, &grandfather_program, None, 0, None, rng).unwrap();
-
-    // Construct the next block.
-    let grandfather_deploy_transfer_block = ledger
-        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![grandfather_deploy_transaction], rng)
-        .unwrap();
-
-    // Cache grandfather block
-    let grandfather_file_path = format!("{}/block_grandfather", transactions_dir);
-    let grandfather_block_bytes = grandfather_deploy_transfer_block.to_bytes_le().expect("Can't serialize into grandfather block to bytes");
-    let mut grandfather_file = File::create(&grandfather_file_path)?;
-    grandfather_file.write(&grandfather_block_bytes).expect("Failed to write grandfather block to file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_deploy_transfer_block).unwrap();
+    let crate::test_helpers::TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    let mut records = records::RecordPool::scan(&ledger, &[accounts::Account::new(private_key)?])?;
+
+    // Deploy `child_spammer.aleo`, then `parent_spammer.aleo`, then `grandfather_spammer.aleo`, caching each block.
+    let spammers = [
+        ("child", programs::child_spammer(16)?),
+        ("parent", programs::parent_spammer(14)),
+        ("grandfather", programs::grandfather_spammer(2)),
+    ];
+    for (name, source) in spammers {
+        let block_name = format!("block_{}", name);
+
+        // `{name}_spammer.aleo` source code
+        let program = Program::<N>::from_str(&source).or_fail(ErrorKind::Config, || format!("parsing {}_spammer.aleo", name))?;
+
+        // Create transaction deploying the program
+        let fee_record = fee_config.fee_record(&mut records, 0)?;
+        let deploy_transaction = ledger
+            .vm()
+            .deploy(&private_key, &program, fee_record, fee_config.priority_fee, None, rng)
+            .or_fail(ErrorKind::Execution, || format!("deploying {}_spammer.aleo", name))?;
+
+        // Construct the next block.
+        let deploy_block = ledger
+            .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![deploy_transaction], rng)
+            .or_fail(ErrorKind::Ledger, || format!("preparing {}", block_name))?;
+
+        // Cache the block
+        cache_block(transactions_dir, &block_name, &deploy_block)?;
+
+        // Check that the next block is valid.
+        ledger.check_next_block(&deploy_block).or_fail(ErrorKind::Ledger, || format!("checking {}", block_name))?;
+
+        // Add the deployment block to the ledger.
+        ledger.advance_to_next_block(&deploy_block).or_fail(ErrorKind::Ledger, || format!("advancing to {}", block_name))?;
+    }
 
     Ok(())
 }
 
-fn dummy_file_system_creation() -> io::Result<()> {
+fn dummy_file_system_creation() -> Result<()> {
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
     // Determine the highest number file
     let max_number = find_max_transaction_number(transactions_dir)?;
@@ -597,20 +631,20 @@ fn dummy_file_system_creation() -> io::Result<()> {
         if Path::new(&file_path).exists() {
             println!("File already exists!");
         } else {
-            let mut file = File::create(&file_path)?;
-            writeln!(file, "hello {}", max_number + (i as u32))?;
+            let mut file = File::create(&file_path).or_fail(ErrorKind::Io, || format!("creating {}", file_path))?;
+            writeln!(file, "hello {}", max_number + (i as u32)).or_fail(ErrorKind::Io, || format!("writing {}", file_path))?;
         }
     }
     Ok(())
 }
 
-fn find_max_transaction_number(transactions_dir: &str) -> io::Result<u32> {
-    let re = Regex::new(r"transaction_(\d+)").unwrap();
+fn find_max_transaction_number(transactions_dir: &str) -> Result<u32> {
+    let re = Regex::new(r"transaction_(\d+)").or_fail(ErrorKind::Config, || "compiling the transaction file pattern".into())?;
     let mut max_num = 0;
 
-    for entry in fs::read_dir(transactions_dir)? {
+    for entry in fs::read_dir(transactions_dir).or_fail(ErrorKind::Fixture, || format!("listing {}", transactions_dir))? {
         if let Ok(entry) = entry {
-            if let Some(caps) = re.captures(entry.file_name().to_str().unwrap()) {
+            if let Some(caps) = entry.file_name().to_str().and_then(|name| re.captures(name)) {
                 if let Ok(num) = caps[1].parse::<u32>() {
                     if num > max_num {
                         max_num = num;
@@ -623,48 +657,16 @@ fn find_max_transaction_number(transactions_dir: &str) -> io::Result<u32> {
     Ok(max_num)
 }
 
-fn open_blocks_test() -> io::Result<()> {
+fn open_blocks_test<N: Network>() -> Result<()> {
     // Make sure directory ok
     let transactions_dir = "./transactions";
-    fs::create_dir_all(transactions_dir)?;
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
 
     let rng = &mut TestRng::fixed(6404264900108107703);
 
     // Initialize the test environment.
-    let crate::test_helpers::TestEnv { ledger, private_key, view_key, .. } = crate::test_helpers::sample_test_env(rng);
-
-    // Read child block from bytes
-    let file_path = format!("{}/block_child", transactions_dir);
-    let mut child_file = File::open(&file_path)?;
-    let child_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut child_file).expect("Failed to read child block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&child_deploy_transfer_block).unwrap();
-
-    // Read parent block from bytes
-    let parent_path = format!("{}/block_parent", transactions_dir);
-    let mut parent_file = File::open(&parent_path)?;
-    let parent_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut parent_file).expect("Failed to read parent block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&parent_deploy_transfer_block).unwrap();
-
-    // Read grandfather block from bytes
-    let grandfather_path = format!("{}/block_grandfather", transactions_dir);
-    let mut grandfather_file = File::open(&grandfather_path)?;
-    let grandfather_deploy_transfer_block:Block<CurrentNetwork> = Block::<CurrentNetwork>::read_le(&mut grandfather_file).expect("Failed to read grandfather block from file");
-
-    // Check that the next block is valid.
-    ledger.check_next_block(&grandfather_deploy_transfer_block).unwrap();
-
-    // Add the deployment block to the ledger.
-    ledger.advance_to_next_block(&grandfather_deploy_transfer_block).unwrap();
+    let crate::test_helpers::TestEnv { ledger, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    load_spammer_blocks(&ledger, transactions_dir)?;
 
     Ok(())
 }
@@ -0,0 +1,187 @@
+use crate::fees::FeeBreakdown;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::Network;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// How many IDs of transactions already handed to the block producer are remembered for deduplication. Older ones are
+/// forgotten, so long runs don't grow without bound; by then the ledger knows them.
+const RECENT_IDS: usize = 1 << 16;
+
+/// A transaction waiting in the mempool.
+pub(crate) struct Pending<T> {
+    pub transaction: T,
+    /// When the transaction was created, or received if that is unknown.
+    pub created: Instant,
+    pub priority_fee: u64,
+    // Submission order, to break ties between equal priority fees.
+    sequence: u64,
+}
+
+impl<T> PartialEq for Pending<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Pending<T> {}
+
+impl<T> PartialOrd for Pending<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Pending<T> {
+    // Highest priority fee first, then first come first served.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority_fee, Reverse(self.sequence)).cmp(&(other.priority_fee, Reverse(other.sequence)))
+    }
+}
+
+/// Why the mempool turned a transaction away.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Refusal {
+    /// A transaction with the same ID is pending or was recently handed to the block producer.
+    Duplicate,
+}
+
+/// The mempool's bookkeeping, over any ID and transaction type.
+struct State<K, T> {
+    pending: BinaryHeap<Pending<T>>,
+    // IDs of the pending transactions and of the last `recent_capacity` taken ones, so resubmissions are dropped.
+    seen: HashSet<K>,
+    recent: VecDeque<K>,
+    recent_capacity: usize,
+    next_sequence: u64,
+    duplicates: usize,
+}
+
+impl<K: Clone + Eq + Hash, T> State<K, T> {
+    fn new(recent_capacity: usize) -> Self {
+        Self { pending: BinaryHeap::new(), seen: HashSet::new(), recent: VecDeque::new(), recent_capacity, next_sequence: 0, duplicates: 0 }
+    }
+
+    fn insert(&mut self, id: K, transaction: T, priority_fee: u64, created: Instant) -> std::result::Result<(), Refusal> {
+        if !self.seen.insert(id) {
+            self.duplicates += 1;
+            return Err(Refusal::Duplicate);
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.pending.push(Pending { transaction, created, priority_fee, sequence });
+        Ok(())
+    }
+
+    /// Pops up to `max` transactions, remembering their IDs in `recent` and forgetting the oldest beyond its capacity.
+    fn take(&mut self, max: usize, id: impl Fn(&T) -> K) -> Vec<Pending<T>> {
+        let take = self.pending.len().min(max);
+        let batch = (0..take).filter_map(|_| self.pending.pop()).collect::<Vec<_>>();
+        self.recent.extend(batch.iter().map(|pending| id(&pending.transaction)));
+        while self.recent.len() > self.recent_capacity {
+            if let Some(forgotten) = self.recent.pop_front() {
+                self.seen.remove(&forgotten);
+            }
+        }
+        batch
+    }
+}
+
+/// A simulated node mempool: deduplicates submitted transactions and hands them to the block producer highest
+/// priority fee first.
+pub(crate) struct Mempool<N: Network> {
+    state: Mutex<State<N::TransactionID, Transaction<N>>>,
+}
+
+impl<N: Network> Mempool<N> {
+    pub(crate) fn new() -> Self {
+        Self { state: Mutex::new(State::new(RECENT_IDS)) }
+    }
+
+    /// Adds `transaction`, created at `created`, unless it was submitted before. Its priority fee is whatever it pays
+    /// beyond its base cost.
+    pub(crate) fn submit<C: ConsensusStorage<N>>(
+        &self,
+        ledger: &Ledger<N, C>,
+        transaction: Transaction<N>,
+        created: Instant,
+    ) -> std::result::Result<(), Refusal> {
+        // Work out the fee outside the lock; a transaction whose cost can't be computed sorts last.
+        let priority_fee = FeeBreakdown::new(ledger, &transaction).map(|fee| fee.priority()).unwrap_or(0);
+        self.lock().insert(transaction.id(), transaction, priority_fee, created)
+    }
+
+    /// Removes and returns up to `max` transactions, highest priority fee first.
+    pub(crate) fn take_batch(&self, max: usize) -> Vec<Pending<Transaction<N>>> {
+        self.lock().take(max, Transaction::id)
+    }
+
+    /// The number of pending transactions.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// The number of submissions dropped as duplicates.
+    pub(crate) fn duplicates(&self) -> usize {
+        self.lock().duplicates
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State<N::TransactionID, Transaction<N>>> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(batch: &[Pending<&'static str>]) -> Vec<&'static str> {
+        batch.iter().map(|pending| pending.transaction).collect()
+    }
+
+    fn submit(state: &mut State<&'static str, &'static str>, id: &'static str, priority_fee: u64) -> std::result::Result<(), Refusal> {
+        state.insert(id, id, priority_fee, Instant::now())
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut state = State::new(16);
+        assert_eq!(submit(&mut state, "a", 1), Ok(()));
+        assert_eq!(submit(&mut state, "a", 5), Err(Refusal::Duplicate));
+        assert_eq!(state.pending.len(), 1);
+        assert_eq!(state.duplicates, 1);
+
+        // Taken transactions are still remembered.
+        state.take(1, |id| *id);
+        assert_eq!(submit(&mut state, "a", 1), Err(Refusal::Duplicate));
+        assert_eq!(state.duplicates, 2);
+    }
+
+    #[test]
+    fn pops_highest_priority_fee_first_then_in_submission_order() {
+        let mut state = State::new(16);
+        for (id, priority_fee) in [("a", 5), ("b", 9), ("c", 5), ("d", 0), ("e", 9)] {
+            submit(&mut state, id, priority_fee).unwrap();
+        }
+        assert_eq!(ids(&state.take(3, |id| *id)), ["b", "e", "a"]);
+        assert_eq!(ids(&state.take(3, |id| *id)), ["c", "d"]);
+        assert!(state.take(3, |id| *id).is_empty());
+    }
+
+    #[test]
+    fn forgets_the_oldest_taken_ids_beyond_capacity() {
+        let mut state = State::new(2);
+        for id in ["a", "b", "c"] {
+            submit(&mut state, id, 0).unwrap();
+        }
+        state.take(3, |id| *id);
+        assert_eq!(state.seen.len(), 2);
+        assert_eq!(submit(&mut state, "a", 0), Ok(()));
+        assert_eq!(submit(&mut state, "c", 0), Err(Refusal::Duplicate));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::accounts::Account;
+use crate::assertions;
+use crate::error::{ErrorKind, OrFail, Result};
+use crate::fees::FeeConfig;
+use crate::preload;
+use crate::programs::{self, FinalizeOp};
+use crate::records::RecordPool;
+use crate::test_helpers::*;
+use crate::workload;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::prelude::{Network, Program, TestRng};
+use std::fs;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The program `create-mix-block` deploys and `verify-mix` and `preload` call.
+pub(crate) const MIX_PROGRAM_ID: &str = "mix_spammer.aleo";
+/// The number of `spam` executions in the block `verify-mix` times.
+const MIX_TX_NUM: usize = 32;
+
+/// The finalize ops run by every `mix_spammer.aleo/spam` transaction.
+/// Together with the leading `set` into `map[0u8]` this makes exactly the 16 writes testnet3 allows in one finalize.
+fn mix_ops() -> Vec<FinalizeOp> {
+    programs::default_finalize_mix(2, 7)
+}
+
+/// Deploys `mix_spammer.aleo` on top of the cached spammer blocks and caches the deployment as `block_mix`.
+pub(crate) fn create_mix_block<N: Network>(fee_config: FeeConfig) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    load_spammer_blocks(&ledger, transactions_dir)?;
+
+    // `mix_spammer.aleo` source code
+    let mix_program = Program::<N>::from_str(&programs::finalize_mix::<N>(MIX_PROGRAM_ID, &mix_ops())?)
+        .or_fail(ErrorKind::Config, || format!("parsing {}", MIX_PROGRAM_ID))?;
+
+    // Create transaction deploying `mix_spammer.aleo`
+    let mut records = RecordPool::scan(&ledger, &[Account::new(private_key)?])?;
+    let fee_record = fee_config.fee_record(&mut records, 0)?;
+    let mix_deploy_transaction = ledger
+        .vm()
+        .deploy(&private_key, &mix_program, fee_record, fee_config.priority_fee, None, rng)
+        .or_fail(ErrorKind::Execution, || format!("deploying {}", MIX_PROGRAM_ID))?;
+
+    // Construct the next block.
+    let mix_deploy_block = ledger
+        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![mix_deploy_transaction], rng)
+        .or_fail(ErrorKind::Ledger, || "preparing block_mix".into())?;
+
+    // Cache mix block
+    cache_block(transactions_dir, "block_mix", &mix_deploy_block)?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&mix_deploy_block).or_fail(ErrorKind::Ledger, || "checking block_mix".into())?;
+
+    // Add the deployment block to the ledger.
+    ledger.advance_to_next_block(&mix_deploy_block).or_fail(ErrorKind::Ledger, || "advancing to block_mix".into())?;
+
+    Ok(())
+}
+
+/// Times a block of `MIX_TX_NUM` `mix_spammer.aleo/spam` executions and checks the mappings they leave behind. Keys
+/// into `large_map` are aimed at preloaded entries when there are any.
+pub(crate) fn verify_finalize_mix<N: Network, C: ConsensusStorage<N>>(snapshot_dir: Option<&str>, dev: Option<u16>) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment, which needs `block_mix` to have been created.
+    let TestEnv { ledger, private_key, address, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let preloaded_entries = preload::preloaded_entries(transactions_dir)?;
+
+    // When `large_map` has been preloaded, aim the random keys at existing entries.
+    let mut workload = workload::finalize_mix(MIX_PROGRAM_ID, &mix_ops());
+    if preloaded_entries > 0 {
+        workload.inputs = vec![workload::InputSpec::RandomU64Below(preloaded_entries as u64)];
+    }
+
+    // Create the transactions, each with its own random key into `large_map`, and expect the entries it sets there.
+    let mut mix_transactions = Vec::new();
+    for i in 0..MIX_TX_NUM {
+        let r = &mut TestRng::default();
+        let execute_inputs = workload.sample_inputs::<N>(r)?;
+        workload.expected_mappings.extend(workload::random_set_expectations(MIX_PROGRAM_ID, &mix_ops(), &address, &execute_inputs[0])?);
+        let new_tx = ledger
+            .vm()
+            .execute(&private_key, (workload.program_id.as_str(), workload.function_name.as_str()), execute_inputs.into_iter(), None, 0, None, r)
+            .or_fail(ErrorKind::Execution, || format!("executing mix transaction {}", i))?;
+        mix_transactions.push(new_tx);
+        println!("{}/{} completed!", i + 1, MIX_TX_NUM);
+    }
+
+    // Start the timer
+    let start = Instant::now();
+
+    // Construct the next block.
+    let mix_execute_block = ledger
+        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], mix_transactions, rng)
+        .or_fail(ErrorKind::Ledger, || "preparing the mix execution block".into())?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&mix_execute_block).or_fail(ErrorKind::Ledger, || "checking the mix execution block".into())?;
+
+    // Add the execution block to the ledger.
+    ledger.advance_to_next_block(&mix_execute_block).or_fail(ErrorKind::Ledger, || "advancing to the mix execution block".into())?;
+
+    // Stop the timer
+    let duration = start.elapsed();
+
+    // Print the duration
+    println!("Time elapsed is: {:?}", duration);
+    println!("Time elapsed per transactions is {:?}", duration / MIX_TX_NUM as u32);
+    println!("Finalize ops per transaction: {}", mix_ops().len());
+    println!("Preloaded entries: {}", preloaded_entries);
+
+    // Check that finalize left the mappings in the expected state.
+    assertions::check_mapping_expectations(&ledger, &workload.expected_mappings)?;
+
+    Ok(())
+}
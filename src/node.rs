@@ -0,0 +1,257 @@
+use crate::error::{catch_panic, ErrorKind, OrFail, Result};
+use crate::http::{self, Request};
+use crate::mempool::{Mempool, Refusal};
+use crate::test_helpers::*;
+use serde_json::json;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Identifier, Network, Plaintext, PrivateKey, ProgramID, TestRng};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Every endpoint lives under the network name, like on a node. Only testnet3 is supported.
+const API_PREFIX: &str = "/testnet3";
+/// The most connections handled at once; further ones are answered with a 503 and closed.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Serves a stand-in for a node's REST API on `address`, backed by the fixture ledger:
+///  - `POST /testnet3/transaction/broadcast` checks a JSON transaction and adds it to a `Mempool`,
+///  - `GET /testnet3/latest/height`, `/latest/block`, `/block/{height}` and `/transaction/{id}`,
+///  - `GET /testnet3/program/{id}/mapping/{name}/{key}`.
+/// A block producer advances the ledger from the mempool every `block_interval`. Runs until the process is killed, or
+/// exits if the block producer panics, since the ledger would no longer advance.
+pub(crate) fn serve<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    address: &str,
+    block_interval: Duration,
+    max_block_txs: usize,
+    dev: Option<u16>,
+) -> Result<()> {
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let mempool = Mempool::<N>::new();
+
+    let listener = TcpListener::bind(address).or_fail(ErrorKind::Io, || format!("binding {}", address))?;
+    println!("Serving {}{} at height {}, a block every {:?}", address, API_PREFIX, ledger.latest_height(), block_interval);
+
+    let connections = AtomicUsize::new(0);
+    thread::scope(|scope| -> Result<()> {
+        let (ledger, mempool, private_key, connections) = (&ledger, &mempool, &private_key, &connections);
+        thread::Builder::new()
+            .name("block-producer".to_string())
+            .spawn_scoped(scope, move || {
+                let result = catch_panic(|| {
+                    produce_blocks(ledger, private_key, mempool, block_interval, max_block_txs);
+                    Ok(())
+                });
+                if let Err(error) = result {
+                    let error = error.with_context("running the block producer");
+                    error.print_summary("serve");
+                    std::process::exit(error.kind.exit_code());
+                }
+            })
+            .or_fail(ErrorKind::Thread, || "spawning the block producer".into())?;
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Accepting a connection failed: {}", error);
+                    continue;
+                }
+            };
+            if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                let _ = http::write_response(&stream, 503, &json!("Too many connections").to_string());
+                continue;
+            }
+            let spawned = thread::Builder::new().name("request".to_string()).spawn_scoped(scope, move || {
+                // A panicking request must still give back its connection slot.
+                let result = catch_panic(|| handle_connection(ledger, mempool, &stream));
+                connections.fetch_sub(1, Ordering::SeqCst);
+                if let Err(error) = result {
+                    eprintln!("Request from {:?} failed: {}", stream.peer_addr().ok(), error);
+                }
+            });
+            // Returning would wait on the block producer forever, so drop the connection and carry on.
+            if let Err(error) = spawned {
+                connections.fetch_sub(1, Ordering::SeqCst);
+                eprintln!("Spawning a request handler failed: {}", error);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Builds a block from the highest paying pending transactions every `block_interval`. A block the ledger refuses is
+/// dropped along with its transactions, as a node would drop them.
+fn produce_blocks<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    mempool: &Mempool<N>,
+    block_interval: Duration,
+    max_block_txs: usize,
+) {
+    let rng = &mut TestRng::default();
+    loop {
+        thread::sleep(block_interval);
+        let transactions = mempool.take_batch(max_block_txs).into_iter().map(|pending| pending.transaction).collect::<Vec<_>>();
+        if transactions.is_empty() {
+            continue;
+        }
+        let result = ledger
+            .prepare_advance_to_next_beacon_block(private_key, vec![], vec![], transactions, rng)
+            .and_then(|block| ledger.check_next_block(&block).map(|()| block))
+            .and_then(|block| ledger.advance_to_next_block(&block).map(|()| block));
+        match result {
+            Ok(block) => println!(
+                "Block {}: {} accepted, {} rejected, {} pending",
+                block.height(),
+                block.transactions().num_accepted(),
+                block.transactions().num_rejected(),
+                mempool.len()
+            ),
+            Err(error) => eprintln!("Dropping a block: {}", error),
+        }
+    }
+}
+
+fn handle_connection<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, mempool: &Mempool<N>, stream: &TcpStream) -> Result<()> {
+    let request = match http::read_request(stream) {
+        Ok(request) => request,
+        Err(error) => {
+            // Tell the client why before dropping the connection. It may be gone already, so only report the parse error.
+            let _ = http::write_response(stream, 400, &json!(error.to_string()).to_string());
+            return Err(error);
+        }
+    };
+    let (status, body) = match route(ledger, mempool, &request) {
+        Ok(body) => (200, body),
+        Err((status, message)) => (status, json!(message).to_string()),
+    };
+    http::write_response(stream, status, &body)
+}
+
+/// An endpoint of the API, with the path segments it takes, still unparsed.
+#[derive(Debug, PartialEq, Eq)]
+enum Endpoint<'a> {
+    Broadcast,
+    LatestHeight,
+    LatestBlock,
+    Block(&'a str),
+    Transaction(&'a str),
+    Mapping { program_id: &'a str, mapping_name: &'a str, key: &'a str },
+}
+
+impl<'a> Endpoint<'a> {
+    /// Finds the endpoint `method path` asks for, or the HTTP status and reason it names none.
+    fn parse(method: &str, path: &'a str) -> std::result::Result<Self, (u16, String)> {
+        let unknown = || (404, format!("Unknown path `{}`", path));
+        let segments = path.strip_prefix(API_PREFIX).ok_or_else(unknown)?.trim_matches('/').split('/').collect::<Vec<_>>();
+        let (endpoint, allowed) = match segments[..] {
+            ["transaction", "broadcast"] => (Endpoint::Broadcast, "POST"),
+            ["latest", "height"] => (Endpoint::LatestHeight, "GET"),
+            ["latest", "block"] => (Endpoint::LatestBlock, "GET"),
+            ["block", height] => (Endpoint::Block(height), "GET"),
+            ["transaction", id] => (Endpoint::Transaction(id), "GET"),
+            ["program", program_id, "mapping", mapping_name, key] => (Endpoint::Mapping { program_id, mapping_name, key }, "GET"),
+            _ => return Err(unknown()),
+        };
+        if method != allowed {
+            return Err((405, format!("{} is not allowed on `{}`", method, path)));
+        }
+        Ok(endpoint)
+    }
+}
+
+/// Answers `request` with a JSON body, or an HTTP status and the reason for it.
+fn route<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    mempool: &Mempool<N>,
+    request: &Request,
+) -> std::result::Result<String, (u16, String)> {
+    let bad_request = |error: &dyn std::fmt::Display| (400, error.to_string());
+    let not_found = |error: &dyn std::fmt::Display| (404, error.to_string());
+    let encode = |value: serde_json::Result<String>| value.map_err(|error| (500, error.to_string()));
+
+    match Endpoint::parse(&request.method, &request.path)? {
+        Endpoint::Broadcast => {
+            let transaction = serde_json::from_slice::<Transaction<N>>(&request.body).map_err(|error| bad_request(&error))?;
+            let id = transaction.id();
+            if ledger.contains_transaction_id(&id).unwrap_or(false) {
+                return Err((409, format!("Transaction {} is already on the ledger", id)));
+            }
+            ledger.vm().check_transaction(&transaction, None, &mut TestRng::default()).map_err(|error| (422, error.to_string()))?;
+            match mempool.submit(ledger, transaction, Instant::now()) {
+                Ok(()) => Ok(json!(id.to_string()).to_string()),
+                Err(Refusal::Duplicate) => Err((409, format!("Transaction {} is already pending", id))),
+            }
+        }
+        Endpoint::LatestHeight => Ok(json!(ledger.latest_height()).to_string()),
+        Endpoint::LatestBlock => {
+            let block = ledger.get_block(ledger.latest_height()).map_err(|error| (500, error.to_string()))?;
+            encode(serde_json::to_string(&block))
+        }
+        Endpoint::Block(height) => {
+            let height = height.parse::<u32>().map_err(|error| bad_request(&error))?;
+            let block = ledger.get_block(height).map_err(|error| not_found(&error))?;
+            encode(serde_json::to_string(&block))
+        }
+        Endpoint::Transaction(id) => {
+            let id = N::TransactionID::from_str(id).map_err(|error| bad_request(&error))?;
+            let transaction = ledger.get_transaction(id).map_err(|error| not_found(&error))?;
+            encode(serde_json::to_string(&transaction))
+        }
+        Endpoint::Mapping { program_id, mapping_name, key } => {
+            let program_id = ProgramID::<N>::from_str(program_id).map_err(|error| bad_request(&error))?;
+            let mapping_name = Identifier::<N>::from_str(mapping_name).map_err(|error| bad_request(&error))?;
+            let key = Plaintext::<N>::from_str(key).map_err(|error| bad_request(&error))?;
+            let value = ledger.vm().finalize_store().get_value_confirmed(program_id, mapping_name, &key).map_err(|error| not_found(&error))?;
+            // A missing entry is `null`, like on a node.
+            Ok(json!(value.map(|value| value.to_string())).to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(method: &str, path: &str) -> Option<u16> {
+        Endpoint::parse(method, path).err().map(|(status, _)| status)
+    }
+
+    #[test]
+    fn parses_known_endpoints() {
+        assert_eq!(Endpoint::parse("POST", "/testnet3/transaction/broadcast"), Ok(Endpoint::Broadcast));
+        assert_eq!(Endpoint::parse("GET", "/testnet3/latest/height"), Ok(Endpoint::LatestHeight));
+        assert_eq!(Endpoint::parse("GET", "/testnet3/latest/block/"), Ok(Endpoint::LatestBlock));
+        assert_eq!(Endpoint::parse("GET", "/testnet3/block/12"), Ok(Endpoint::Block("12")));
+        assert_eq!(Endpoint::parse("GET", "/testnet3/transaction/at1xyz"), Ok(Endpoint::Transaction("at1xyz")));
+    }
+
+    #[test]
+    fn splits_mapping_paths() {
+        assert_eq!(
+            Endpoint::parse("GET", "/testnet3/program/child_spammer.aleo/mapping/map/0u8"),
+            Ok(Endpoint::Mapping { program_id: "child_spammer.aleo", mapping_name: "map", key: "0u8" })
+        );
+        assert_eq!(status("GET", "/testnet3/program/child_spammer.aleo/mapping/map"), Some(404));
+        assert_eq!(status("GET", "/testnet3/program/child_spammer.aleo/mapping/map/0u8/extra"), Some(404));
+    }
+
+    #[test]
+    fn rejects_unknown_routes() {
+        assert_eq!(status("GET", "/"), Some(404));
+        assert_eq!(status("GET", "/mainnet/latest/height"), Some(404));
+        assert_eq!(status("GET", "/testnet3/latest/round"), Some(404));
+        assert_eq!(status("GET", "/testnet3/transaction/broadcast"), Some(405));
+        assert_eq!(status("POST", "/testnet3/latest/height"), Some(405));
+    }
+}
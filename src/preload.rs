@@ -0,0 +1,172 @@
+use crate::error::{join_worker, Error, ErrorKind, OrFail, Result};
+use crate::programs::PRELOAD_ENTRIES_PER_TX;
+use crate::test_helpers::*;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::{Network, TestRng, Value};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+use std::{fs, thread};
+
+/// Records how many `large_map` entries the cached `block_preload_*` blocks write.
+const PRELOAD_INFO_FILE: &str = "preload_info";
+/// Records the `<entries> <txs_per_block>` the cached `block_preload_*` blocks were made with.
+const PRELOAD_SETTINGS_FILE: &str = "preload_settings";
+
+fn preload_block_name(index: usize) -> String {
+    format!("block_preload_{}", index)
+}
+
+/// Fills `large_map` of `program_id` with at least `entries` entries, `txs_per_block` `preload` calls per block.
+/// Blocks that are already cached are replayed instead of regenerated, so an interrupted preload can be resumed with
+/// the same `entries` and `txs_per_block`.
+pub(crate) fn preload_mapping<N: Network>(program_id: &str, entries: usize, txs_per_block: usize) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+    check_preload_settings(transactions_dir, entries, txs_per_block)?;
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = sample_test_env::<N, ConsensusMemory<N>>(rng, None)?;
+    load_spammer_blocks(&ledger, transactions_dir)?;
+    load_cached_block(&ledger, transactions_dir, "block_mix")?;
+
+    let num_txs = (entries + PRELOAD_ENTRIES_PER_TX - 1) / PRELOAD_ENTRIES_PER_TX; // Round up
+    let num_blocks = (num_txs + txs_per_block - 1) / txs_per_block; // Round up
+    let num_cpus = num_cpus::get();
+
+    // Start the timer
+    let start = Instant::now();
+
+    for block_index in 0..num_blocks {
+        let block_name = preload_block_name(block_index);
+        let first_tx = block_index * txs_per_block;
+        let block_txs = txs_per_block.min(num_txs - first_tx);
+
+        if Path::new(&format!("{}/{}", transactions_dir, block_name)).exists() {
+            load_cached_block(&ledger, transactions_dir, &block_name)?;
+            println!("Replayed cached {}", block_name);
+            continue;
+        }
+
+        // Split this block's `preload` calls across threads.
+        let mut handles = Vec::new();
+        let work_per_thread = (block_txs + num_cpus - 1) / num_cpus; // Round up
+        for i in 0..num_cpus {
+            let ledger_ref = ledger.clone();
+            let program_id = program_id.to_string();
+            let first = first_tx + i * work_per_thread;
+            let last = (first + work_per_thread).min(first_tx + block_txs);
+            let handle = thread::Builder::new()
+                .name(format!("preload-{}", i))
+                .spawn(move || {
+                    let r = &mut TestRng::default();
+                    (first..last)
+                        .map(|tx_index| {
+                            let base = Value::<N>::from_str(&format!("{}u64", tx_index * PRELOAD_ENTRIES_PER_TX))
+                                .or_fail(ErrorKind::Execution, || format!("building the input of preload transaction {}", tx_index))?;
+                            ledger_ref
+                                .vm()
+                                .execute(&private_key, (program_id.as_str(), "preload"), vec![base].into_iter(), None, 0, None, r)
+                                .or_fail(ErrorKind::Execution, || format!("executing preload transaction {}", tx_index))
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .or_fail(ErrorKind::Thread, || format!("spawning preload-{}", i))?;
+            handles.push(handle);
+        }
+        let mut transactions = Vec::new();
+        for handle in handles {
+            transactions.extend(join_worker(handle)?);
+        }
+
+        // Construct the next block.
+        let block = ledger
+            .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transactions, rng)
+            .or_fail(ErrorKind::Ledger, || format!("preparing {}", block_name))?;
+
+        // Check that the next block is valid.
+        ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || format!("checking {}", block_name))?;
+
+        // Add the preload block to the ledger.
+        ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to {}", block_name))?;
+
+        // Cache the block and how many entries the cached blocks now cover.
+        cache_block(transactions_dir, &block_name, &block)?;
+        let preloaded = (first_tx + block_txs) * PRELOAD_ENTRIES_PER_TX;
+        let info_path = format!("{}/{}", transactions_dir, PRELOAD_INFO_FILE);
+        fs::write(&info_path, preloaded.to_string()).or_fail(ErrorKind::Io, || format!("writing {}", info_path))?;
+
+        println!("{}/{} preload blocks completed ({} entries)", block_index + 1, num_blocks, preloaded);
+    }
+
+    // Stop the timer
+    let duration = start.elapsed();
+    println!("Time elapsed is: {:?}", duration);
+    println!("Preloaded {} entries in {} blocks", num_txs * PRELOAD_ENTRIES_PER_TX, num_blocks);
+
+    Ok(())
+}
+
+/// Checks that the cached preload blocks in `dir`, if any, were made with `entries` and `txs_per_block`, since resuming
+/// with other settings would cache blocks that don't line up. Records the settings when nothing is cached yet.
+fn check_preload_settings(dir: &str, entries: usize, txs_per_block: usize) -> Result<()> {
+    let settings_path = format!("{}/{}", dir, PRELOAD_SETTINGS_FILE);
+    let settings = format!("{} {}", entries, txs_per_block);
+    if !Path::new(&format!("{}/{}", dir, preload_block_name(0))).exists() {
+        return fs::write(&settings_path, &settings).or_fail(ErrorKind::Io, || format!("writing {}", settings_path));
+    }
+    let cached = fs::read_to_string(&settings_path).or_fail(ErrorKind::Fixture, || format!("reading {}", settings_path))?;
+    if cached.trim() != settings {
+        return Err(Error::new(
+            ErrorKind::Config,
+            format!(
+                "The cached preload blocks in {} were made with `<entries> <txs-per-block>` of `{}`, not `{}`; delete them to preload again",
+                dir,
+                cached.trim(),
+                settings
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Replays every cached `block_preload_*` block, which must follow `block_mix`.
+/// Returns the number of `large_map` entries they wrote, or zero if nothing has been preloaded.
+pub(crate) fn load_preload_blocks<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, dir: &str) -> Result<usize> {
+    let entries = preloaded_entries(dir)?;
+    if entries == 0 {
+        return Ok(0);
+    }
+
+    let mut block_index = 0;
+    while Path::new(&format!("{}/{}", dir, preload_block_name(block_index))).exists() {
+        load_cached_block(ledger, dir, &preload_block_name(block_index))?;
+        block_index += 1;
+    }
+    println!("Replayed {} preload blocks ({} entries)", block_index, entries);
+
+    Ok(entries)
+}
+
+/// The number of `large_map` entries written by the cached preload blocks in `dir`.
+pub(crate) fn preloaded_entries(dir: &str) -> Result<usize> {
+    let info_path = format!("{}/{}", dir, PRELOAD_INFO_FILE);
+    if !Path::new(&info_path).exists() {
+        return Ok(0);
+    }
+    fs::read_to_string(&info_path).or_fail(ErrorKind::Fixture, || format!("reading {}", info_path))?.trim().parse::<usize>().or_fail(ErrorKind::Fixture, || format!("reading {}", info_path))
+}
+
+/// Replays `block_mix` and the preload blocks if they have been generated, otherwise does nothing.
+pub(crate) fn load_preloaded_state<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, dir: &str) -> Result<usize> {
+    if !Path::new(&format!("{}/block_mix", dir)).exists() {
+        return Ok(0);
+    }
+    load_cached_block(ledger, dir, "block_mix")?;
+    load_preload_blocks(ledger, dir)
+}
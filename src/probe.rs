@@ -0,0 +1,199 @@
+use crate::error::{ErrorKind, OrFail, Result};
+use crate::programs;
+use crate::test_helpers::*;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Network, PrivateKey, Program, TestRng, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How long one probe took, and how many finalize operations its block ran.
+struct Timing {
+    execute: Duration,
+    block: Duration,
+    finalize_operations: usize,
+}
+
+/// Finds the largest finalize workloads the VM accepts, using generated programs:
+///  - the most `set`s in one finalize, with `probe_set_<n>.aleo` programs,
+///  - the most calls of that program in one transaction, with `probe_calls_<k>.aleo` programs,
+///  - the most of those transactions in one block.
+/// Each probe prints its timing, so the output doubles as the timing curve up to each limit.
+pub(crate) fn probe_finalize_limits<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    max_sets: usize,
+    max_calls: usize,
+    max_block_txs: usize,
+    dev: Option<u16>,
+) -> Result<()> {
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+
+    println!("Probing finalize writes per scope (up to {})", max_sets);
+    let max_sets = search(max_sets, |num_sets| {
+        let program_name = format!("probe_set_{}.aleo", num_sets);
+        probe_program(&ledger, &private_key, &programs::set_spammer(&program_name, num_sets)?, &program_name, "spam", num_sets, rng)
+    })?;
+    println!("Most writes in one finalize: {}", max_sets);
+    if max_sets == 0 {
+        return Ok(());
+    }
+
+    println!("\nProbing calls of probe_set_{}.aleo/spam per transaction (up to {})", max_sets, max_calls);
+    let callee = format!("probe_set_{}.aleo", max_sets);
+    let max_calls = search(max_calls, |num_calls| {
+        let program_name = format!("probe_calls_{}.aleo", num_calls);
+        let source = programs::call_spammer(&program_name, "main", &[callee.as_str()], &format!("{}/spam", callee), num_calls);
+        probe_program(&ledger, &private_key, &source, &program_name, "main", num_calls * max_sets, rng)
+    })?;
+    println!("Most calls in one transaction: {} ({} writes)", max_calls, max_calls * max_sets);
+    if max_calls == 0 {
+        return Ok(());
+    }
+
+    println!("\nProbing probe_calls_{}.aleo/main transactions per block (up to {})", max_calls, max_block_txs);
+    let locator = format!("probe_calls_{}.aleo", max_calls);
+    let mut transactions = Vec::new();
+    let max_block_txs = search(max_block_txs, |num_txs| {
+        // Reuse the transactions built by earlier probes; only the block differs.
+        let execute_start = Instant::now();
+        while transactions.len() < num_txs {
+            transactions.push(execute_main(&ledger, &private_key, &locator, rng)?);
+        }
+        let execute = execute_start.elapsed();
+        let outcome = build_block(&ledger, &private_key, transactions[..num_txs].to_vec(), false, rng);
+        Ok(report(num_txs, num_txs * max_calls * max_sets, outcome.map(|(block, finalize_operations)| Timing { execute, block, finalize_operations })))
+    })?;
+    println!("Most transactions in one block: {} ({} writes)", max_block_txs, max_block_txs * max_calls * max_sets);
+
+    Ok(())
+}
+
+/// Finds the largest `n` in `1..=max` for which `probe(n)` holds, assuming it holds for every smaller `n`: doubles
+/// until the probe fails, then bisects. Each `n` is probed at most once. Returns zero if it fails for 1.
+fn search(max: usize, mut probe: impl FnMut(usize) -> Result<bool>) -> Result<usize> {
+    let mut results = BTreeMap::new();
+    let mut check = |n: usize| -> Result<bool> {
+        if let Some(result) = results.get(&n) {
+            return Ok(*result);
+        }
+        let result = probe(n)?;
+        results.insert(n, result);
+        Ok(result)
+    };
+
+    let (mut fits, mut limit) = (0, max + 1);
+    let mut n = 1;
+    while n <= max {
+        if !check(n)? {
+            limit = n;
+            break;
+        }
+        fits = n;
+        n *= 2;
+    }
+    while fits + 1 < limit {
+        let mid = (fits + limit) / 2;
+        if check(mid)? {
+            fits = mid;
+        } else {
+            limit = mid;
+        }
+    }
+    Ok(fits)
+}
+
+/// Parses and deploys `source`, executes `program_name/function_name` once and advances to a block with the execution.
+/// Returns whether the VM accepted all of it.
+fn probe_program<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    source: &str,
+    program_name: &str,
+    function_name: &str,
+    writes: usize,
+    rng: &mut TestRng,
+) -> Result<bool> {
+    // The VM enforces some limits, such as writes per finalize, while parsing, so a parse failure is a rejection too.
+    let program = match Program::<N>::from_str(source) {
+        Ok(program) => program,
+        Err(error) => return Ok(report(writes, writes, Err(format!("parse failed: {}", error)))),
+    };
+    let deployment = match ledger.vm().deploy(private_key, &program, None, 0, None, rng) {
+        Ok(deployment) => deployment,
+        Err(error) => return Ok(report(writes, writes, Err(format!("deploy failed: {}", error)))),
+    };
+    if let Err(error) = build_block(ledger, private_key, vec![deployment], true, rng) {
+        return Ok(report(writes, writes, Err(format!("deployment block failed: {}", error))));
+    }
+
+    let execute_start = Instant::now();
+    let transaction = match ledger.vm().execute(private_key, (program_name, function_name), Vec::<Value<N>>::new().into_iter(), None, 0, None, rng) {
+        Ok(transaction) => transaction,
+        Err(error) => return Ok(report(writes, writes, Err(format!("execute failed: {}", error)))),
+    };
+    let execute = execute_start.elapsed();
+    let outcome = build_block(ledger, private_key, vec![transaction], true, rng);
+    Ok(report(writes, writes, outcome.map(|(block, finalize_operations)| Timing { execute, block, finalize_operations })))
+}
+
+fn execute_main<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    program_name: &str,
+    rng: &mut TestRng,
+) -> Result<Transaction<N>> {
+    ledger
+        .vm()
+        .execute(private_key, (program_name, "main"), Vec::<Value<N>>::new().into_iter(), None, 0, None, rng)
+        .or_fail(ErrorKind::Execution, || format!("executing {}/main", program_name))
+}
+
+/// Builds and checks a block of `transactions`, advancing to it if `advance` is set. Fails if any transaction is
+/// dropped. Returns how long building and checking took and how many finalize operations the block ran.
+fn build_block<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    transactions: Vec<Transaction<N>>,
+    advance: bool,
+    rng: &mut TestRng,
+) -> std::result::Result<(Duration, usize), String> {
+    let num_transactions = transactions.len();
+    let start = Instant::now();
+    let block = ledger
+        .prepare_advance_to_next_beacon_block(private_key, vec![], vec![], transactions, rng)
+        .map_err(|error| format!("prepare failed: {}", error))?;
+    ledger.check_next_block(&block).map_err(|error| format!("check failed: {}", error))?;
+    let elapsed = start.elapsed();
+
+    let accepted = block.transactions().num_accepted();
+    if accepted < num_transactions {
+        return Err(format!("only {}/{} transactions were accepted", accepted, num_transactions));
+    }
+    if advance {
+        ledger.advance_to_next_block(&block).map_err(|error| format!("advance failed: {}", error))?;
+    }
+    let finalize_operations = block.transactions().iter().map(|confirmed| confirmed.finalize_operations().len()).sum();
+    Ok((elapsed, finalize_operations))
+}
+
+/// Prints one point of the timing curve and returns whether the probe passed.
+fn report(n: usize, writes: usize, outcome: std::result::Result<Timing, String>) -> bool {
+    match outcome {
+        Ok(timing) => {
+            println!(
+                "{:>6}: ok, {:>6} writes, {:>6} finalize operations, execute {:?}, block {:?}",
+                n, writes, timing.finalize_operations, timing.execute, timing.block
+            );
+            true
+        }
+        Err(reason) => {
+            println!("{:>6}: rejected, {}", n, reason);
+            false
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::{Error, ErrorKind, Result};
+use snarkvm::prelude::Network;
+use snarkvm::synthesizer::program::{Command, Function};
+use std::fmt::Write;
+
+/// The number of `set` and `remove` commands in `function`'s finalize, or zero if it has none.
+pub(crate) fn finalize_writes<N: Network>(function: &Function<N>) -> usize {
+    function.finalize_logic().map_or(0, |finalize| {
+        finalize.commands().iter().filter(|command| matches!(command, Command::Set(_) | Command::Remove(_))).count()
+    })
+}
+
+/// `child_spammer.aleo`, whose `spam` finalize writes `i` into `map[i]` for `i` in `0..num_sets`.
+pub(crate) fn child_spammer(num_sets: usize) -> Result<String> {
+    set_spammer("child_spammer.aleo", num_sets)
+}
+
+/// `parent_spammer.aleo`, whose `main` calls `child_spammer.aleo/spam` `num_calls` times and awaits each future.
+pub(crate) fn parent_spammer(num_calls: usize) -> String {
+    call_spammer("parent_spammer.aleo", "main", &["child_spammer.aleo"], "child_spammer.aleo/spam", num_calls)
+}
+
+/// `grandfather_spammer.aleo`, whose `outer_most_call` calls `parent_spammer.aleo/main` `num_calls` times.
+pub(crate) fn grandfather_spammer(num_calls: usize) -> String {
+    call_spammer(
+        "grandfather_spammer.aleo",
+        "outer_most_call",
+        &["child_spammer.aleo", "parent_spammer.aleo"],
+        "parent_spammer.aleo/main",
+        num_calls,
+    )
+}
+
+/// A program like `child_spammer.aleo` named `program_name`, whose `spam` finalize writes `i` into `map[i]`
+/// for `i` in `0..num_sets`. Fails if `num_sets` is more than the 256 keys a `u8` has.
+pub(crate) fn set_spammer(program_name: &str, num_sets: usize) -> Result<String> {
+    if num_sets > 256 {
+        return Err(Error::new(ErrorKind::Config, format!("{} can set at most 256 u8 keys, not {}", program_name, num_sets)));
+    }
+    let mut source = format!(
+        r"
+program {program_name};
+
+mapping map:
+	key as u8.public;
+	value as u8.public;
+
+function spam:
+    async spam into r0;
+    output r0 as {program_name}/spam.future;
+
+finalize spam:"
+    );
+    for i in 0..num_sets {
+        write!(source, "\n    set {i}u8 into map[{i}u8];").unwrap();
+    }
+    Ok(source)
+}
+
+/// A program named `program_name` whose `function_name` calls `callee` (`program/function`, which takes no inputs
+/// and returns a future) `num_calls` times and awaits each future in its finalize. `imports` must list `callee`'s
+/// program and everything it imports.
+pub(crate) fn call_spammer(program_name: &str, function_name: &str, imports: &[&str], callee: &str, num_calls: usize) -> String {
+    let mut source = String::from("\n");
+    for import in imports {
+        writeln!(source, "import {import};").unwrap();
+    }
+    write!(source, "program {program_name};\n\nfunction {function_name}:\n").unwrap();
+    for i in 0..num_calls {
+        writeln!(source, "    call {callee} into r{i};").unwrap();
+    }
+    let futures = (0..num_calls).map(|i| format!("r{i}")).collect::<Vec<_>>().join(" ");
+    writeln!(source, "    async {function_name} {futures} into r{num_calls};").unwrap();
+    writeln!(source, "    output r{num_calls} as {program_name}/{function_name}.future;\n\nfinalize {function_name}:").unwrap();
+    for i in 0..num_calls {
+        writeln!(source, "    input r{i} as {callee}.future;").unwrap();
+    }
+    for i in 0..num_calls {
+        writeln!(source, "    await r{i};").unwrap();
+    }
+    source
+}
+
+/// A program named `program_name` with `num_functions` functions `hash_<i>`, each of which hashes its input
+/// `num_hashes` times with BHP256. Both grow the circuits a deployment synthesizes keys for.
+pub(crate) fn hash_spammer(program_name: &str, num_functions: usize, num_hashes: usize) -> String {
+    let mut source = format!("\nprogram {program_name};\n");
+    for f in 0..num_functions {
+        write!(source, "\nfunction hash_{f}:\n    input r0 as field.private;\n").unwrap();
+        for i in 0..num_hashes {
+            writeln!(source, "    hash.bhp256 r{i} into r{} as field;", i + 1).unwrap();
+        }
+        writeln!(source, "    output r{num_hashes} as field.private;").unwrap();
+    }
+    source
+}
+
+/// A single finalize operation emitted by `finalize_mix`.
+///
+/// Every generated `spam` finalize receives the caller as `r0` and a random `u64` as `r1`,
+/// and starts by writing `0u8` into `map[0u8]` so that reads of that key always succeed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FinalizeOp {
+    /// `set` a constant `u8` into `map`, like `child_spammer.aleo`.
+    Set,
+    /// `get` the always-present `map[0u8]`.
+    Get,
+    /// `get.or_use` a constant key of `map` that may not exist.
+    GetOrUse,
+    /// `contains` on `large_map` at the random key.
+    Contains,
+    /// `get.or_use` `large_map` at the random key, defaulting to an empty `entry`.
+    LargeGet,
+    /// `set` then `remove` the scratch key `map[255u8]`.
+    Remove,
+    /// Wrapping `add` and `mul` on the random input.
+    Arithmetic,
+    /// `hash.bhp256` of the random input.
+    Hash,
+    /// `cast` the random input into a field.
+    Cast,
+    /// `branch.neq` past a hash, so the hash only runs when the random input is zero.
+    Branch,
+    /// `set` an `entry` struct into `large_map` at a key derived from the random input.
+    RandomSet,
+}
+
+/// The number of consecutive `large_map` keys written by one `preload` call of a `finalize_mix` program, one `set`
+/// each. This is `N::MAX_WRITES` on testnet3; `finalize_mix` checks it still fits.
+pub(crate) const PRELOAD_ENTRIES_PER_TX: usize = 16;
+
+impl FinalizeOp {
+    pub(crate) const ALL: [FinalizeOp; 11] = [
+        FinalizeOp::Set,
+        FinalizeOp::Get,
+        FinalizeOp::GetOrUse,
+        FinalizeOp::Contains,
+        FinalizeOp::LargeGet,
+        FinalizeOp::Remove,
+        FinalizeOp::Arithmetic,
+        FinalizeOp::Hash,
+        FinalizeOp::Cast,
+        FinalizeOp::Branch,
+        FinalizeOp::RandomSet,
+    ];
+
+    /// The number of `set`/`remove` commands the op emits.
+    pub(crate) fn writes(&self) -> usize {
+        match self {
+            FinalizeOp::Set | FinalizeOp::RandomSet => 1,
+            FinalizeOp::Remove => 2,
+            _ => 0,
+        }
+    }
+
+    /// The constant `map` key written by the `index`-th `Set`, avoiding the scratch key `255u8`.
+    pub(crate) fn set_key(index: usize) -> u8 {
+        (index % 255) as u8
+    }
+}
+
+/// A finalize mix that exercises every op `repeat` times, followed by `random_sets` extra writes into `large_map`.
+/// Note the generated finalize also writes `map[0u8]` up front, which counts against `N::MAX_WRITES`.
+pub(crate) fn default_finalize_mix(repeat: usize, random_sets: usize) -> Vec<FinalizeOp> {
+    let mut ops = Vec::new();
+    for _ in 0..repeat {
+        ops.extend_from_slice(&FinalizeOp::ALL);
+    }
+    ops.extend(std::iter::repeat(FinalizeOp::RandomSet).take(random_sets));
+    ops
+}
+
+/// Generates a program named `program_name` whose `spam` finalize runs `ops` in order.
+/// The program also has a `preload` function that fills `large_map[base..base + PRELOAD_ENTRIES_PER_TX]`.
+/// Fails if `ops` and the leading write into `map[0u8]`, or `preload`, exceed the `N::MAX_WRITES` the VM allows.
+pub(crate) fn finalize_mix<N: Network>(program_name: &str, ops: &[FinalizeOp]) -> Result<String> {
+    let max_writes = N::MAX_WRITES as usize;
+    let writes = 1 + ops.iter().map(FinalizeOp::writes).sum::<usize>();
+    for (function, writes) in [("spam", writes), ("preload", PRELOAD_ENTRIES_PER_TX)] {
+        if writes > max_writes {
+            return Err(Error::new(
+                ErrorKind::Config,
+                format!("The {} finalize of {} would write {} times, more than the {} allowed", function, program_name, writes, max_writes),
+            ));
+        }
+    }
+    let mut source = format!(
+        r"
+program {program_name};
+
+struct entry:
+    owner as address;
+    counter as u64;
+    digest as field;
+
+mapping map:
+	key as u8.public;
+	value as u8.public;
+
+mapping large_map:
+	key as u64.public;
+	value as entry.public;
+
+function spam:
+    input r0 as u64.public;
+    async spam self.caller r0 into r1;
+    output r1 as {program_name}/spam.future;
+
+finalize spam:
+    input r0 as address.public;
+    input r1 as u64.public;
+    set 0u8 into map[0u8];
+"
+    );
+
+    // The next free register in the finalize scope.
+    let mut next = 2usize;
+    let mut fresh = || {
+        next += 1;
+        next - 1
+    };
+    let mut num_sets = 0;
+
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            FinalizeOp::Set => {
+                let key = FinalizeOp::set_key(num_sets);
+                num_sets += 1;
+                writeln!(source, "    set {key}u8 into map[{key}u8];").unwrap();
+            }
+            FinalizeOp::Get => {
+                writeln!(source, "    get map[0u8] into r{};", fresh()).unwrap();
+            }
+            FinalizeOp::GetOrUse => {
+                writeln!(source, "    get.or_use map[{}u8] 0u8 into r{};", i % 255, fresh()).unwrap();
+            }
+            FinalizeOp::Contains => {
+                writeln!(source, "    contains large_map[r1] into r{};", fresh()).unwrap();
+            }
+            FinalizeOp::LargeGet => {
+                let (default, value) = (fresh(), fresh());
+                writeln!(source, "    cast r0 0u64 0field into r{default} as entry;").unwrap();
+                writeln!(source, "    get.or_use large_map[r1] r{default} into r{value};").unwrap();
+            }
+            FinalizeOp::Remove => {
+                writeln!(source, "    set 0u8 into map[255u8];\n    remove map[255u8];").unwrap();
+            }
+            FinalizeOp::Arithmetic => {
+                let (sum, product) = (fresh(), fresh());
+                writeln!(source, "    add.w r1 {i}u64 into r{sum};\n    mul.w r{sum} 3u64 into r{product};").unwrap();
+            }
+            FinalizeOp::Hash => {
+                writeln!(source, "    hash.bhp256 r1 into r{} as field;", fresh()).unwrap();
+            }
+            FinalizeOp::Cast => {
+                writeln!(source, "    cast r1 into r{} as field;", fresh()).unwrap();
+            }
+            FinalizeOp::Branch => {
+                writeln!(source, "    branch.neq r1 0u64 to skip_{i};").unwrap();
+                writeln!(source, "    hash.bhp256 r1 into r{} as field;", fresh()).unwrap();
+                writeln!(source, "    position skip_{i};").unwrap();
+            }
+            FinalizeOp::RandomSet => {
+                let (key, digest, value) = (fresh(), fresh(), fresh());
+                writeln!(source, "    add.w r1 {i}u64 into r{key};").unwrap();
+                writeln!(source, "    hash.bhp256 r{key} into r{digest} as field;").unwrap();
+                writeln!(source, "    cast r0 r{key} r{digest} into r{value} as entry;").unwrap();
+                writeln!(source, "    set r{value} into large_map[r{key}];").unwrap();
+            }
+        }
+    }
+
+    write!(
+        source,
+        r"
+function preload:
+    input r0 as u64.public;
+    async preload self.caller r0 into r1;
+    output r1 as {program_name}/preload.future;
+
+finalize preload:
+    input r0 as address.public;
+    input r1 as u64.public;
+"
+    )
+    .unwrap();
+    for j in 0..PRELOAD_ENTRIES_PER_TX {
+        let (key, digest, value) = (2 + 3 * j, 3 + 3 * j, 4 + 3 * j);
+        writeln!(source, "    add r1 {j}u64 into r{key};").unwrap();
+        writeln!(source, "    hash.bhp256 r{key} into r{digest} as field;").unwrap();
+        writeln!(source, "    cast r0 r{key} r{digest} into r{value} as entry;").unwrap();
+        writeln!(source, "    set r{value} into large_map[r{key}];").unwrap();
+    }
+    Ok(source)
+}
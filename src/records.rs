@@ -0,0 +1,263 @@
+use crate::accounts::{self, Account};
+use crate::error::{Context, Error, ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::{Ledger, RecordsFilter};
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Entry, Field, Identifier, Literal, Network, Plaintext, Record, TestRng, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// A decrypted, unspent credits record and the pool account that owns it.
+#[derive(Clone)]
+pub(crate) struct OwnedRecord<N: Network> {
+    pub owner: usize,
+    pub commitment: Field<N>,
+    pub record: Record<N, Plaintext<N>>,
+}
+
+impl<N: Network> OwnedRecord<N> {
+    /// The record's `microcredits` entry.
+    pub(crate) fn microcredits(&self) -> Result<u64> {
+        let identifier = Identifier::from_str("microcredits").or_fail(ErrorKind::Config, || "parsing `microcredits`".into())?;
+        match self.record.data().get(&identifier) {
+            Some(Entry::Private(Plaintext::Literal(Literal::U64(microcredits), _))) => Ok(**microcredits),
+            _ => Err(Error::new(ErrorKind::Fixture, format!("Record {} is not a credits record", self.commitment))),
+        }
+    }
+}
+
+/// The records a corpus may spend. Every record is handed out at most once, so the transactions generated from one
+/// `RecordPool` never double-spend each other.
+pub(crate) struct RecordPool<N: Network> {
+    // Unspent records per account, indexed like the account slice they were found for.
+    unspent: Vec<Vec<OwnedRecord<N>>>,
+    consumed: HashSet<Field<N>>,
+}
+
+impl<N: Network> RecordPool<N> {
+    /// Scans the ledger for the unspent records of each account and decrypts them with the account's view key.
+    pub(crate) fn scan<C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, accounts: &[Account<N>]) -> Result<Self> {
+        let mut unspent = Vec::with_capacity(accounts.len());
+        for (owner, account) in accounts.iter().enumerate() {
+            let records = ledger
+                .find_records(&account.view_key, RecordsFilter::Unspent)
+                .or_fail(ErrorKind::Ledger, || format!("scanning the records of account {}", owner))?
+                .map(|(commitment, record)| {
+                    let record = record
+                        .decrypt(&account.view_key)
+                        .or_fail(ErrorKind::Ledger, || format!("decrypting record {} of account {}", commitment, owner))?;
+                    Ok(OwnedRecord { owner, commitment, record })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            unspent.push(records);
+        }
+        Ok(Self { unspent, consumed: HashSet::new() })
+    }
+
+    /// The number of records that have not been handed out yet.
+    pub(crate) fn len(&self) -> usize {
+        self.unspent.iter().map(Vec::len).sum()
+    }
+
+    /// Hands out `count` records owned by the same account, preferring the account with the most records left.
+    /// Returns `None` if no account has that many.
+    pub(crate) fn take(&mut self, count: usize) -> Result<Option<Vec<OwnedRecord<N>>>> {
+        match (0..self.unspent.len()).max_by_key(|owner| self.unspent[*owner].len()) {
+            Some(owner) => self.take_from(owner, count),
+            None => Ok(None),
+        }
+    }
+
+    /// Hands out `count` records owned by the account at index `owner`, or `None` if it has fewer left.
+    pub(crate) fn take_from(&mut self, owner: usize, count: usize) -> Result<Option<Vec<OwnedRecord<N>>>> {
+        match self.unspent.get(owner) {
+            Some(unspent) if unspent.len() >= count => {}
+            _ => return Ok(None),
+        }
+        let records = self.unspent[owner].split_off(self.unspent[owner].len() - count);
+        for record in &records {
+            // `scan` only finds each commitment once, so this can only fail if a record was put back.
+            if !self.consumed.insert(record.commitment) {
+                return Err(Error::new(ErrorKind::Assertion, format!("Record {} was handed out twice", record.commitment)));
+            }
+        }
+        Ok(Some(records))
+    }
+}
+
+/// The `credits.aleo` record-spending functions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RecordSpend {
+    /// `transfer_private` half of a record to another account.
+    TransferPrivate,
+    /// `split` a record in two.
+    Split,
+    /// `join` two records of the same account.
+    Join,
+}
+
+impl RecordSpend {
+    pub(crate) const ALL: [RecordSpend; 3] = [RecordSpend::TransferPrivate, RecordSpend::Split, RecordSpend::Join];
+
+    pub(crate) fn function_name(self) -> &'static str {
+        match self {
+            RecordSpend::TransferPrivate => "transfer_private",
+            RecordSpend::Split => "split",
+            RecordSpend::Join => "join",
+        }
+    }
+
+    pub(crate) fn num_records(self) -> usize {
+        match self {
+            RecordSpend::Join => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl FromStr for RecordSpend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        RecordSpend::ALL
+            .into_iter()
+            .find(|spend| spend.function_name() == s)
+            .ok_or_else(|| format!("expected one of transfer_private, split or join, found `{}`", s))
+    }
+}
+
+/// Creates one transaction per spend, cycling through `spends`, until the pool runs out of records that can be
+/// spent. Each record is spent at most once. Fees are paid from the owner's public balance.
+pub(crate) fn spend_records<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    accounts: &[Account<N>],
+    records: &mut RecordPool<N>,
+    spends: &[RecordSpend],
+    max_transactions: usize,
+    rng: &mut TestRng,
+) -> Result<Vec<Transaction<N>>> {
+    let mut transactions = Vec::new();
+    for spend in spends.iter().cycle().take(max_transactions) {
+        let Some(inputs) = records.take(spend.num_records())? else {
+            break;
+        };
+        let transaction = spend_transaction(ledger, accounts, *spend, &inputs, rng).context(|| format!("creating spend {}", transactions.len()))?;
+        transactions.push(transaction);
+    }
+    Ok(transactions)
+}
+
+/// Creates a transaction that spends `inputs`, which must all belong to the same account, with `spend`.
+/// Nothing stops the same records from being spent twice; use a `RecordPool` for that.
+pub(crate) fn spend_transaction<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    accounts: &[Account<N>],
+    spend: RecordSpend,
+    inputs: &[OwnedRecord<N>],
+    rng: &mut TestRng,
+) -> Result<Transaction<N>> {
+    if inputs.len() != spend.num_records() {
+        return Err(Error::new(ErrorKind::Config, format!("{} spends {} records, not {}", spend.function_name(), spend.num_records(), inputs.len())));
+    }
+    let owner = inputs[0].owner;
+    let mut values = inputs.iter().map(|input| Value::Record(input.record.clone())).collect::<Vec<Value<N>>>();
+    match spend {
+        RecordSpend::TransferPrivate => {
+            let receiver = &accounts[(owner + 1) % accounts.len()];
+            values.push(Value::from_str(&receiver.address.to_string()).or_fail(ErrorKind::Config, || "parsing the receiver".into())?);
+            values.push(amount_value(inputs[0].microcredits()? / 2)?);
+        }
+        RecordSpend::Split => values.push(amount_value(inputs[0].microcredits()? / 2)?),
+        RecordSpend::Join => {}
+    }
+    ledger
+        .vm()
+        .execute(&accounts[owner].private_key, ("credits.aleo", spend.function_name()), values.into_iter(), None, 0, None, rng)
+        .or_fail(ErrorKind::Execution, || format!("executing {} for account {}", spend.function_name(), owner))
+}
+
+fn amount_value<N: Network>(microcredits: u64) -> Result<Value<N>> {
+    Value::from_str(&format!("{}u64", microcredits)).or_fail(ErrorKind::Config, || format!("parsing {}u64", microcredits))
+}
+
+/// Checks that no two transactions spend the same record.
+pub(crate) fn check_no_double_spends<N: Network>(transactions: &[Transaction<N>]) -> Result<()> {
+    let mut serial_numbers = HashSet::new();
+    for transaction in transactions {
+        for serial_number in transaction.serial_numbers() {
+            if !serial_numbers.insert(*serial_number) {
+                return Err(Error::new(
+                    ErrorKind::Assertion,
+                    format!("Transaction {} spends serial number {} a second time", transaction.id(), serial_number),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Times a block of record spends shaped by `spends`, funded by the genesis and pool accounts' records. Every spend
+/// must be accepted.
+pub(crate) fn spam_records<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: Option<&str>,
+    spends: &[RecordSpend],
+    max_transactions: usize,
+    dev: Option<u16>,
+) -> Result<()> {
+    // Make sure directory ok
+    let transactions_dir = "./transactions";
+    fs::create_dir_all(transactions_dir).or_fail(ErrorKind::Io, || format!("creating {}", transactions_dir))?;
+
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment. The genesis account owns the genesis records, the pool its funding records.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, snapshot_dir)?;
+    let mut accounts = vec![Account::new(private_key)?];
+    accounts.extend(accounts::derive_pool::<N>(accounts::pool_size(transactions_dir)?)?);
+
+    // Find and decrypt the records we may spend.
+    let scan_start = Instant::now();
+    let mut record_pool = RecordPool::scan(&ledger, &accounts)?;
+    println!("Found {} unspent records across {} accounts in {:?}", record_pool.len(), accounts.len(), scan_start.elapsed());
+
+    // Create the spends, each consuming records no other transaction in the block consumes.
+    let r = &mut TestRng::default();
+    let create_start = Instant::now();
+    let transactions = spend_records(&ledger, &accounts, &mut record_pool, spends, max_transactions, r)?;
+    println!("Created {} record spends in {:?}", transactions.len(), create_start.elapsed());
+    check_no_double_spends(&transactions)?;
+    let num_transactions = transactions.len();
+
+    // Start the timer
+    let start = Instant::now();
+
+    // Construct the next block.
+    let spend_block = ledger
+        .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], transactions, rng)
+        .or_fail(ErrorKind::Ledger, || "preparing the record spend block".into())?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&spend_block).or_fail(ErrorKind::Ledger, || "checking the record spend block".into())?;
+
+    // Add the record spend block to the ledger.
+    ledger.advance_to_next_block(&spend_block).or_fail(ErrorKind::Ledger, || "advancing to the record spend block".into())?;
+
+    // Stop the timer
+    let duration = start.elapsed();
+
+    // Print the duration
+    println!("Time elapsed is: {:?}", duration);
+    println!("Time elapsed per transactions is {:?}", duration / num_transactions.max(1) as u32);
+    println!("Records left unspent: {}", record_pool.len());
+
+    // None of the spends conflict, so all of them must be accepted; a rejected spend still lands in the block.
+    let accepted = spend_block.transactions().num_accepted();
+    if accepted != num_transactions {
+        return Err(Error::new(ErrorKind::Assertion, format!("Only {}/{} record spends were accepted", accepted, num_transactions)));
+    }
+
+    Ok(())
+}
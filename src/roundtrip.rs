@@ -0,0 +1,115 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use snarkvm::prelude::block::{Block, Transaction};
+use snarkvm::prelude::{FromBytes, Network, ToBytes};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Records the ID of every cached transaction and the hash of every cached block, one `<file> <id>` per line, so a
+/// snarkVM change that alters them is caught.
+const IDS_FILE: &str = "ids";
+
+/// Checks that every cached block and transaction in `dir` survives the byte, string and JSON round trips unchanged,
+/// and that its ID matches the one recorded in `ids`. Missing IDs are recorded; with `update_ids` every ID is
+/// rewritten instead of compared.
+pub(crate) fn check_corpus_roundtrips<N: Network>(dir: &str, update_ids: bool) -> Result<()> {
+    check_fixture_network::<N>(dir)?;
+    let ids_path = format!("{}/{}", dir, IDS_FILE);
+    let mut recorded = if Path::new(&ids_path).exists() && !update_ids { read_ids(&ids_path)? } else { BTreeMap::new() };
+    let mut current = BTreeMap::new();
+    let mut failures = Vec::new();
+
+    let mut block_names = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries.map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string())).collect::<std::io::Result<Vec<_>>>()
+        })
+        .or_fail(ErrorKind::Fixture, || format!("listing {}", dir))?;
+    block_names.retain(|name| name.starts_with("block_"));
+    block_names.sort();
+
+    let mut files = block_names.into_iter().map(|name| (name, true)).collect::<Vec<_>>();
+    files.extend(list_transactions(dir)?.into_iter().map(|(number, _)| (format!("transaction_{}", number), false)));
+
+    for (name, is_block) in &files {
+        let path = format!("{}/{}", dir, name);
+        let bytes = fs::read(&path).or_fail(ErrorKind::Fixture, || format!("reading {}", path))?;
+        let id = if *is_block {
+            check_roundtrip::<Block<N>>(&path, &bytes).map(|block| block.hash().to_string())
+        } else {
+            check_roundtrip::<Transaction<N>>(&path, &bytes).map(|transaction| transaction.id().to_string())
+        };
+        match id {
+            Ok(id) => {
+                if let Some(expected) = recorded.remove(name) {
+                    if expected != id {
+                        failures.push(format!("{}: ID changed from {} to {}", path, expected, id));
+                    }
+                }
+                current.insert(name.clone(), id);
+            }
+            Err(error) => failures.push(error.to_string()),
+        }
+    }
+
+    // Record any IDs we have not seen before, unless something diverged and the old IDs are needed to compare against.
+    if failures.is_empty() || update_ids {
+        let ids = current.iter().map(|(name, id)| format!("{} {}\n", name, id)).collect::<String>();
+        fs::write(&ids_path, ids).or_fail(ErrorKind::Io, || format!("writing {}", ids_path))?;
+    }
+
+    println!("Checked {} cached files", files.len());
+    if !failures.is_empty() {
+        for failure in &failures {
+            eprintln!("Round trip failed: {}", failure);
+        }
+        return Err(Error::new(ErrorKind::Assertion, format!("{}/{} cached files failed to round trip", failures.len(), files.len())));
+    }
+    Ok(())
+}
+
+/// Decodes `bytes`, and checks that re-encoding them as bytes, a string and JSON gives back the same object and bytes.
+fn check_roundtrip<T>(path: &str, bytes: &[u8]) -> Result<T>
+where
+    T: FromBytes + ToBytes + FromStr + Display + Serialize + DeserializeOwned + PartialEq,
+    <T as FromStr>::Err: Display,
+{
+    let object = T::read_le(bytes).or_fail(ErrorKind::Fixture, || format!("decoding {}", path))?;
+
+    // bytes -> object -> bytes
+    let reencoded = object.to_bytes_le().or_fail(ErrorKind::Fixture, || format!("re-encoding {}", path))?;
+    if reencoded != bytes {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}: re-encoding changed {} bytes into {}", path, bytes.len(), reencoded.len())));
+    }
+
+    // object -> string -> object
+    let from_string = T::from_str(&object.to_string()).or_fail(ErrorKind::Assertion, || format!("parsing the string encoding of {}", path))?;
+    if from_string != object {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}: the string encoding does not round trip", path)));
+    }
+
+    // object -> JSON -> object
+    let json = serde_json::to_value(&object).or_fail(ErrorKind::Assertion, || format!("encoding {} as JSON", path))?;
+    let from_json = serde_json::from_value::<T>(json).or_fail(ErrorKind::Assertion, || format!("decoding the JSON encoding of {}", path))?;
+    if from_json != object || from_json.to_bytes_le().ok().as_deref() != Some(bytes) {
+        return Err(Error::new(ErrorKind::Assertion, format!("{}: the JSON encoding does not round trip", path)));
+    }
+
+    Ok(object)
+}
+
+fn read_ids(path: &str) -> Result<BTreeMap<String, String>> {
+    fs::read_to_string(path)
+        .or_fail(ErrorKind::Fixture, || format!("reading {}", path))?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once(' ') {
+            Some((name, id)) => Ok((name.to_string(), id.trim().to_string())),
+            None => Err(Error::new(ErrorKind::Fixture, format!("Malformed line `{}` in {}", line, path))),
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+#[cfg(feature = "rocks")]
+use crate::error::Error;
+use crate::error::{ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::ledger::store::ConsensusStorage;
+use snarkvm::ledger::Ledger;
+use snarkvm::prelude::block::Block;
+use snarkvm::prelude::{Address, FromBytes, Network, PrivateKey, TestRng, ToBytes, ViewKey};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+#[cfg(feature = "rocks")]
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+use std::fs;
+
+// A snapshot directory holds:
+//  - `network`: the ID of the network the snapshot was taken on, as for the fixture directory.
+//  - `private_key`: the genesis private key, so the snapshot's owner can keep spending from it.
+//  - `height`: the height of the latest block in the snapshot.
+//  - `blocks`: every block from genesis onwards, serialized back to back.
+//  - `rocksdb/`: a copy of the RocksDB storage, only when saved from the `rocks` backend.
+const PRIVATE_KEY_FILE: &str = "private_key";
+const HEIGHT_FILE: &str = "height";
+const BLOCKS_FILE: &str = "blocks";
+#[cfg(feature = "rocks")]
+const ROCKSDB_DIR: &str = "rocksdb";
+
+/// Builds the fixture ledger (cached deployments plus any preloaded state), advances `extra_blocks` empty blocks
+/// on top of it, and saves the result to `snapshot_dir`.
+pub(crate) fn create_snapshot<N: Network, C: ConsensusStorage<N>>(
+    snapshot_dir: &str,
+    extra_blocks: u32,
+    dev: Option<u16>,
+) -> Result<()> {
+    let rng = &mut TestRng::fixed(6404264900108107703);
+
+    // Initialize the test environment.
+    let TestEnv { ledger, private_key, .. } = load_fixture_env::<N, C>(rng, dev, None)?;
+
+    // Advance empty blocks to reach the requested height.
+    for i in 0..extra_blocks {
+        let block = ledger
+            .prepare_advance_to_next_beacon_block(&private_key, vec![], vec![], vec![], rng)
+            .or_fail(ErrorKind::Ledger, || format!("preparing empty block {}", i))?;
+        ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || format!("checking empty block {}", i))?;
+        ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to empty block {}", i))?;
+    }
+
+    save_snapshot(&ledger, &private_key, snapshot_dir, dev)
+}
+
+/// Saves `ledger` to `snapshot_dir`. With the RocksDB backend (`dev` is set), the storage is copied as well,
+/// so nothing may be writing to the ledger while this runs.
+pub(crate) fn save_snapshot<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    private_key: &PrivateKey<N>,
+    snapshot_dir: &str,
+    dev: Option<u16>,
+) -> Result<()> {
+    fs::create_dir_all(snapshot_dir).or_fail(ErrorKind::Io, || format!("creating {}", snapshot_dir))?;
+    tag_fixture_network::<N>(snapshot_dir)?;
+    let start = Instant::now();
+
+    let height = ledger.latest_height();
+    let (private_key_path, height_path) = (format!("{}/{}", snapshot_dir, PRIVATE_KEY_FILE), format!("{}/{}", snapshot_dir, HEIGHT_FILE));
+    fs::write(&private_key_path, private_key.to_string()).or_fail(ErrorKind::Io, || format!("writing {}", private_key_path))?;
+    fs::write(&height_path, height.to_string()).or_fail(ErrorKind::Io, || format!("writing {}", height_path))?;
+
+    // Write every block, genesis included.
+    let blocks_path = format!("{}/{}", snapshot_dir, BLOCKS_FILE);
+    let mut blocks_file = BufWriter::new(File::create(&blocks_path).or_fail(ErrorKind::Io, || format!("creating {}", blocks_path))?);
+    for h in 0..=height {
+        let block = ledger.get_block(h).or_fail(ErrorKind::Ledger, || format!("reading block {} from the ledger", h))?;
+        block.write_le(&mut blocks_file).or_fail(ErrorKind::Io, || format!("writing block {} to the snapshot", h))?;
+    }
+    blocks_file.flush().or_fail(ErrorKind::Io, || format!("writing {}", blocks_path))?;
+
+    #[cfg(feature = "rocks")]
+    if let Some(dev) = dev {
+        let storage_path = aleo_std::aleo_ledger_dir(N::ID, Some(dev));
+        let rocksdb_copy = Path::new(snapshot_dir).join(ROCKSDB_DIR);
+        copy_dir_all(&storage_path, &rocksdb_copy)
+            .or_fail(ErrorKind::Io, || format!("copying {} to {}", storage_path.display(), rocksdb_copy.display()))?;
+    }
+    #[cfg(not(feature = "rocks"))]
+    let _ = dev;
+
+    println!("Saved snapshot at height {} to {} in {:?}", height, snapshot_dir, start.elapsed());
+    Ok(())
+}
+
+/// Opens the ledger saved in `snapshot_dir`.
+///
+/// If the snapshot holds a RocksDB copy and the RocksDB backend is selected, the copy is restored into the (empty)
+/// storage and reopened directly. Otherwise the saved blocks are replayed with `advance_to_next_block`, skipping
+/// `check_next_block` since they were checked when the snapshot was made.
+pub(crate) fn open_snapshot<N: Network, C: ConsensusStorage<N>>(snapshot_dir: &str, dev: Option<u16>) -> Result<TestEnv<N, C>> {
+    check_fixture_network::<N>(snapshot_dir)?;
+    let start = Instant::now();
+
+    let private_key_path = format!("{}/{}", snapshot_dir, PRIVATE_KEY_FILE);
+    let private_key = fs::read_to_string(&private_key_path).or_fail(ErrorKind::Fixture, || format!("reading {}", private_key_path))?;
+    let private_key =
+        PrivateKey::<N>::from_str(private_key.trim()).or_fail(ErrorKind::Fixture, || "reading the snapshot's private key".into())?;
+    let view_key = ViewKey::try_from(&private_key).or_fail(ErrorKind::Fixture, || "deriving the snapshot's view key".into())?;
+    let address = Address::try_from(&private_key).or_fail(ErrorKind::Fixture, || "deriving the snapshot's address".into())?;
+    let height_path = format!("{}/{}", snapshot_dir, HEIGHT_FILE);
+    let height = fs::read_to_string(&height_path)
+        .or_fail(ErrorKind::Fixture, || format!("reading {}", height_path))?
+        .trim()
+        .parse::<u32>()
+        .or_fail(ErrorKind::Fixture, || "reading the snapshot's height".into())?;
+
+    let blocks_path = format!("{}/{}", snapshot_dir, BLOCKS_FILE);
+    let mut blocks_file = BufReader::new(File::open(&blocks_path).or_fail(ErrorKind::Fixture, || format!("opening {}", blocks_path))?);
+    let genesis = Block::<N>::read_le(&mut blocks_file).or_fail(ErrorKind::Fixture, || "reading the snapshot's genesis block".into())?;
+
+    #[cfg(feature = "rocks")]
+    if let Some(dev) = dev {
+        let rocksdb_copy = Path::new(snapshot_dir).join(ROCKSDB_DIR);
+        if rocksdb_copy.exists() {
+            let storage_path = aleo_std::aleo_ledger_dir(N::ID, Some(dev));
+            copy_dir_all(&rocksdb_copy, &storage_path)
+                .or_fail(ErrorKind::Io, || format!("copying {} to {}", rocksdb_copy.display(), storage_path.display()))?;
+
+            let ledger = Ledger::<N, C>::load(genesis, Some(dev)).or_fail(ErrorKind::Ledger, || "reopening the restored RocksDB ledger".into())?;
+            if ledger.latest_height() != height {
+                return Err(Error::new(
+                    ErrorKind::Fixture,
+                    format!("Restored RocksDB ledger is at height {}, expected {}", ledger.latest_height(), height),
+                ));
+            }
+            println!("Reopened snapshot at height {} from {} in {:?}", height, snapshot_dir, start.elapsed());
+            return Ok(TestEnv { ledger, private_key, view_key, address });
+        }
+    }
+
+    // Replay the saved blocks on a fresh ledger.
+    let ledger = Ledger::<N, C>::load(genesis, dev).or_fail(ErrorKind::Ledger, || "loading the snapshot's genesis block".into())?;
+    for h in 1..=height {
+        let block = Block::<N>::read_le(&mut blocks_file).or_fail(ErrorKind::Fixture, || format!("reading block {} from the snapshot", h))?;
+        ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to snapshot block {}", h))?;
+    }
+
+    println!("Replayed snapshot at height {} from {} in {:?}", height, snapshot_dir, start.elapsed());
+    Ok(TestEnv { ledger, private_key, view_key, address })
+}
+
+/// Recursively copies the contents of `from` into `to`, creating `to` if needed.
+#[cfg(feature = "rocks")]
+fn copy_dir_all(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
+    fs::create_dir_all(&to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.as_ref().join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(entry.path(), target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::error::{ErrorKind, OrFail, Result};
+use crate::test_helpers::*;
+use snarkvm::prelude::block::Transaction;
+use snarkvm::prelude::{Identifier, Network, Program, ProgramID, ToBytes};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// A sorted sample, for reporting how a quantity is distributed over the corpus.
+pub(crate) struct Distribution(Vec<u64>);
+
+impl Distribution {
+    pub(crate) fn new(mut values: Vec<u64>) -> Self {
+        values.sort_unstable();
+        Self(values)
+    }
+
+    pub(crate) fn percentile(&self, p: usize) -> u64 {
+        self.0.get((self.0.len().saturating_sub(1)) * p / 100).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn print(&self, name: &str) {
+        let mean = if self.0.is_empty() { 0 } else { self.0.iter().sum::<u64>() / self.0.len() as u64 };
+        println!(
+            "{:<24} min {:>8}  p50 {:>8}  p90 {:>8}  max {:>8}  mean {:>8}",
+            name,
+            self.percentile(0),
+            self.percentile(50),
+            self.percentile(90),
+            self.percentile(100),
+            mean
+        );
+    }
+}
+
+/// Reports what the `transaction_N` fixtures in `dir` contain: how many call each program and function, how large they
+/// are, how many transitions they have, what fees they pay and how many mapping writes their finalize would make.
+/// Programs are taken from the deployments in the cached `block_*` files, so finalize writes are only counted for
+/// those and `credits.aleo`. The fee's own finalize is not counted.
+pub(crate) fn corpus_stats<N: Network>(dir: &str) -> Result<()> {
+    check_fixture_network::<N>(dir)?;
+
+    // Find the programs deployed by the cached blocks, along with `credits.aleo`.
+    let credits = Program::<N>::credits().or_fail(ErrorKind::Config, || "loading credits.aleo".into())?;
+    let mut programs = HashMap::new();
+    programs.insert(*credits.id(), credits);
+    for entry in fs::read_dir(dir).or_fail(ErrorKind::Fixture, || format!("listing {}", dir))? {
+        let name = entry.or_fail(ErrorKind::Fixture, || format!("listing {}", dir))?.file_name().to_string_lossy().to_string();
+        if !name.starts_with("block_") {
+            continue;
+        }
+        let block = read_block::<N>(&format!("{}/{}", dir, name))?;
+        for confirmed in block.transactions().iter() {
+            if let Transaction::Deploy(_, _, deployment, _) = confirmed.transaction() {
+                programs.insert(*deployment.program_id(), deployment.program().clone());
+            }
+        }
+    }
+
+    let mut roots = BTreeMap::<String, usize>::new();
+    let mut transitions = BTreeMap::<String, usize>::new();
+    let (mut sizes, mut transitions_per_tx, mut finalize_per_tx) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut fee_total, mut unknown_finalize) = (0u64, 0usize);
+
+    let corpus = list_transactions(dir)?;
+    for (_, path) in &corpus {
+        let transaction = read_transaction::<N>(path)?;
+        sizes.push(transaction.to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing {}", path))?.len() as u64);
+        if let Some(fee) = transaction.fee_transition() {
+            fee_total += fee.amount().map(|amount| *amount).or_fail(ErrorKind::Fixture, || format!("reading the fee of {}", path))?;
+        }
+
+        let mut num_transitions = 0;
+        for transition in transaction.transitions() {
+            num_transitions += 1;
+            let locator = format!("{}/{}", transition.program_id(), transition.function_name());
+            *transitions.entry(locator).or_default() += 1;
+        }
+        let mut num_finalize = 0;
+        if let Some(execution) = transaction.execution() {
+            for transition in execution.transitions() {
+                match finalize_writes(&programs, transition.program_id(), transition.function_name()) {
+                    Some(writes) => num_finalize += writes,
+                    None => unknown_finalize += 1,
+                }
+            }
+            // `Transaction::transitions` ends with the fee; the execution ends with the root transition.
+            if let Some(root) = execution.transitions().last() {
+                *roots.entry(format!("{}/{}", root.program_id(), root.function_name())).or_default() += 1;
+            }
+        }
+        transitions_per_tx.push(num_transitions);
+        finalize_per_tx.push(num_finalize);
+    }
+
+    println!("Transactions: {}", corpus.len());
+    println!("Known programs: {}", programs.len());
+    println!("\nTransactions by root function:");
+    for (locator, count) in &roots {
+        println!("  {:<48} {}", locator, count);
+    }
+    println!("\nTransitions by function:");
+    for (locator, count) in &transitions {
+        println!("  {:<48} {}", locator, count);
+    }
+    println!();
+    Distribution::new(sizes).print("Serialized size (bytes)");
+    Distribution::new(transitions_per_tx).print("Transitions per tx");
+    Distribution::new(finalize_per_tx).print("Finalize writes per tx");
+    println!("\nFees paid: {} microcredits", fee_total);
+    if unknown_finalize > 0 {
+        println!("Transitions of programs with no cached deployment: {}", unknown_finalize);
+    }
+    Ok(())
+}
+
+/// The number of `set` and `remove` commands in the function's finalize, zero if it has no finalize, or `None` if the
+/// program is unknown.
+fn finalize_writes<N: Network>(
+    programs: &HashMap<ProgramID<N>, Program<N>>,
+    program_id: &ProgramID<N>,
+    function_name: &Identifier<N>,
+) -> Option<u64> {
+    let function = programs.get(program_id)?.get_function(function_name).ok()?;
+    Some(crate::programs::finalize_writes(&function) as u64)
+}
@@ -1,52 +1,248 @@
+use crate::cli::Args;
+use crate::error::{Context, Error, ErrorKind, OrFail, Result};
 use crate::Ledger;
 use snarkvm::console::{
     account::{Address, PrivateKey, ViewKey},
     network::Testnet3,
     prelude::*,
 };
-use snarkvm::ledger::store::ConsensusStore;
-use snarkvm::prelude::block::Block;
+use snarkvm::ledger::store::helpers::memory::ConsensusMemory;
+use snarkvm::ledger::store::{ConsensusStorage, ConsensusStore};
+use snarkvm::prelude::block::{Block, Transaction};
 use snarkvm::synthesizer::vm::VM;
 pub(crate) type CurrentNetwork = Testnet3;
 
-pub(crate) type CurrentLedger =
-Ledger<CurrentNetwork, snarkvm::ledger::store::helpers::memory::ConsensusMemory<CurrentNetwork>>;
-pub(crate) type CurrentConsensusStore =
-ConsensusStore<CurrentNetwork, snarkvm::ledger::store::helpers::memory::ConsensusMemory<CurrentNetwork>>;
-pub(crate) struct TestEnv {
-    pub ledger: CurrentLedger,
-    pub private_key: PrivateKey<CurrentNetwork>,
-    pub view_key: ViewKey<CurrentNetwork>,
-    pub address: Address<CurrentNetwork>,
+#[cfg(feature = "rocks")]
+pub(crate) type RocksConsensus<N> = snarkvm::ledger::store::helpers::rocksdb::ConsensusDB<N>;
+
+pub(crate) struct TestEnv<N: Network, C: ConsensusStorage<N> = ConsensusMemory<N>> {
+    pub ledger: Ledger<N, C>,
+    pub private_key: PrivateKey<N>,
+    pub view_key: ViewKey<N>,
+    pub address: Address<N>,
 }
 
-pub(crate) fn sample_test_env(rng: &mut (impl Rng + CryptoRng)) -> TestEnv {
+/// Samples a genesis private key and opens a ledger for it on the consensus storage `C` with the given dev ID.
+pub(crate) fn sample_test_env<N: Network, C: ConsensusStorage<N>>(
+    rng: &mut (impl Rng + CryptoRng),
+    dev: Option<u16>,
+) -> Result<TestEnv<N, C>> {
     // Sample the genesis private key.
-    let private_key = PrivateKey::<CurrentNetwork>::new(rng).unwrap();
-    let view_key = ViewKey::try_from(&private_key).unwrap();
-    let address = Address::try_from(&private_key).unwrap();
+    let private_key = PrivateKey::<N>::new(rng).or_fail(ErrorKind::Ledger, || "sampling the genesis private key".into())?;
+    let view_key = ViewKey::try_from(&private_key).or_fail(ErrorKind::Ledger, || "deriving the view key".into())?;
+    let address = Address::try_from(&private_key).or_fail(ErrorKind::Ledger, || "deriving the address".into())?;
     // Sample the ledger.
-    let ledger = sample_ledger(private_key, rng);
+    let ledger = sample_ledger::<N, C>(private_key, rng, dev)?;
     // Return the test environment.
-    TestEnv { ledger, private_key, view_key, address }
+    Ok(TestEnv { ledger, private_key, view_key, address })
 }
 
-pub(crate) fn sample_genesis_block() -> Block<CurrentNetwork> {
-    Block::<CurrentNetwork>::from_bytes_le(CurrentNetwork::genesis_bytes()).unwrap()
+pub(crate) fn sample_genesis_block<N: Network>() -> Result<Block<N>> {
+    Block::<N>::from_bytes_le(N::genesis_bytes()).or_fail(ErrorKind::Fixture, || "reading the network genesis block".into())
 }
 
-pub(crate) fn sample_ledger(
-    private_key: PrivateKey<CurrentNetwork>,
+pub(crate) fn sample_ledger<N: Network, C: ConsensusStorage<N>>(
+    private_key: PrivateKey<N>,
     rng: &mut (impl Rng + CryptoRng),
-) -> CurrentLedger {
+    dev: Option<u16>,
+) -> Result<Ledger<N, C>> {
     // Initialize the store.
-    let store = CurrentConsensusStore::open(None).unwrap();
+    let store = ConsensusStore::<N, C>::open(dev).or_fail(ErrorKind::Ledger, || "opening the consensus store".into())?;
     // Create a genesis block.
-    let genesis = VM::from(store).unwrap().genesis_beacon(&private_key, rng).unwrap();
+    let genesis = VM::from(store)
+        .and_then(|vm| vm.genesis_beacon(&private_key, rng))
+        .or_fail(ErrorKind::Ledger, || "creating the genesis block".into())?;
     // Initialize the ledger with the genesis block.
-    let ledger = CurrentLedger::load(genesis.clone(), None).unwrap();
+    let ledger = Ledger::<N, C>::load(genesis.clone(), dev).or_fail(ErrorKind::Ledger, || "loading the ledger".into())?;
     // Ensure the genesis block is correct.
-    assert_eq!(genesis, ledger.get_block(0).unwrap());
+    if ledger.get_block(0).ok().as_ref() != Some(&genesis) {
+        return Err(Error::new(ErrorKind::Ledger, "The ledger's genesis block does not match the one it was loaded with"));
+    }
     // Return the ledger.
-    ledger
-}
\ No newline at end of file
+    Ok(ledger)
+}
+
+/// The consensus storage a ledger is opened on.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Backend {
+    /// `ConsensusMemory`, the default.
+    Memory,
+    /// snarkVM's RocksDB `ConsensusDB`, opened under the given dev ID.
+    #[cfg(feature = "rocks")]
+    Rocks { dev: u16 },
+}
+
+/// Reads `--backend memory|rocks`. For `rocks`, `--ledger-dev` picks the storage dev ID (default 0),
+/// `--ledger-dir` relocates that storage to a directory of our choosing, and `--fresh` wipes it first.
+pub(crate) fn backend_from_args<N: Network>(args: &Args) -> Result<Backend> {
+    match args.get_str("backend").unwrap_or("memory") {
+        "memory" => Ok(Backend::Memory),
+        #[cfg(feature = "rocks")]
+        "rocks" => {
+            let dev = args.get("ledger-dev", 0u16)?;
+            prepare_rocks_storage::<N>(dev, args.get_str("ledger-dir"), args.flag("fresh")?)
+                .context(|| format!("preparing the RocksDB storage for dev {}", dev))?;
+            Ok(Backend::Rocks { dev })
+        }
+        backend => Err(Error::new(ErrorKind::Config, format!("Unknown backend `{}` (is the `rocks` feature enabled?)", backend))),
+    }
+}
+
+/// Makes sure the RocksDB storage for `dev` is empty and, if `dir` is given, located there.
+#[cfg(feature = "rocks")]
+pub(crate) fn prepare_rocks_storage<N: Network>(dev: u16, dir: Option<&str>, fresh: bool) -> Result<()> {
+    use std::fs;
+
+    // snarkVM derives the RocksDB path from the network and dev ID, so point that path at `dir`.
+    let storage_path = aleo_std::aleo_ledger_dir(N::ID, Some(dev));
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir).or_fail(ErrorKind::Io, || format!("creating {}", dir))?;
+        let target = fs::canonicalize(dir).or_fail(ErrorKind::Io, || format!("resolving {}", dir))?;
+        match fs::symlink_metadata(&storage_path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                fs::remove_file(&storage_path).or_fail(ErrorKind::Io, || format!("removing the old symlink {}", storage_path.display()))?
+            }
+            Ok(_) => {
+                return Err(Error::new(ErrorKind::Config, format!("{} already exists and is not a symlink", storage_path.display())));
+            }
+            Err(_) => {}
+        }
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent).or_fail(ErrorKind::Io, || format!("creating {}", parent.display()))?;
+        }
+        std::os::unix::fs::symlink(&target, &storage_path)
+            .or_fail(ErrorKind::Io, || format!("linking {} to {}", storage_path.display(), target.display()))?;
+    }
+
+    // The cached blocks are replayed from genesis, so the storage has to start out empty.
+    let describe = || format!("listing {}", storage_path.display());
+    if storage_path.exists() && fs::read_dir(&storage_path).or_fail(ErrorKind::Io, describe)?.next().is_some() {
+        if !fresh {
+            return Err(Error::new(ErrorKind::Config, format!("{} is not empty; pass --fresh to wipe it", storage_path.display())));
+        }
+        for entry in fs::read_dir(&storage_path).or_fail(ErrorKind::Io, describe)? {
+            let path = entry.or_fail(ErrorKind::Io, describe)?.path();
+            if path.is_dir() {
+                fs::remove_dir_all(&path).or_fail(ErrorKind::Io, || format!("removing {}", path.display()))?;
+            } else {
+                fs::remove_file(&path).or_fail(ErrorKind::Io, || format!("removing {}", path.display()))?;
+            }
+        }
+    }
+
+    println!("Opening RocksDB ledger at {}", storage_path.display());
+    Ok(())
+}
+
+/// Names the network that the fixtures in a directory were generated on.
+const NETWORK_TAG_FILE: &str = "network";
+
+/// Records in `dir` that its fixtures belong to the network `N`.
+pub(crate) fn tag_fixture_network<N: Network>(dir: &str) -> Result<()> {
+    let tag_path = format!("{}/{}", dir, NETWORK_TAG_FILE);
+    std::fs::write(&tag_path, N::ID.to_string()).or_fail(ErrorKind::Io, || format!("writing {}", tag_path))
+}
+
+/// Checks that the fixtures in `dir` were generated on the network `N`.
+pub(crate) fn check_fixture_network<N: Network>(dir: &str) -> Result<()> {
+    let tag_path = format!("{}/{}", dir, NETWORK_TAG_FILE);
+    let tag = std::fs::read_to_string(&tag_path)
+        .or_fail(ErrorKind::Fixture, || format!("reading {}, which names the fixtures' network", tag_path))?;
+    if tag.trim() != N::ID.to_string() {
+        return Err(Error::new(
+            ErrorKind::Fixture,
+            format!("Fixtures in {} are for network ID {}, but {} (ID {}) was selected", dir, tag.trim(), N::NAME, N::ID),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the cached block `name` from `dir`, checks it against the ledger, and advances the ledger to it.
+pub(crate) fn load_cached_block<N: Network, C: ConsensusStorage<N>>(
+    ledger: &Ledger<N, C>,
+    dir: &str,
+    name: &str,
+) -> Result<Block<N>> {
+    // Read block from bytes
+    let file_path = format!("{}/{}", dir, name);
+    let block = read_block::<N>(&file_path)?;
+
+    // Check that the next block is valid.
+    ledger.check_next_block(&block).or_fail(ErrorKind::Ledger, || format!("checking the block in {}", file_path))?;
+
+    // Add the block to the ledger.
+    ledger.advance_to_next_block(&block).or_fail(ErrorKind::Ledger, || format!("advancing to the block in {}", file_path))?;
+
+    Ok(block)
+}
+
+/// Reads a serialized block fixture.
+pub(crate) fn read_block<N: Network>(file_path: &str) -> Result<Block<N>> {
+    let mut file = std::fs::File::open(file_path).or_fail(ErrorKind::Fixture, || format!("opening {}", file_path))?;
+    Block::<N>::read_le(&mut file).or_fail(ErrorKind::Fixture, || format!("reading the block in {}", file_path))
+}
+
+/// Reads a serialized `transaction_N` fixture.
+pub(crate) fn read_transaction<N: Network>(file_path: &str) -> Result<Transaction<N>> {
+    let mut file = std::fs::File::open(file_path).or_fail(ErrorKind::Fixture, || format!("opening {}", file_path))?;
+    Transaction::<N>::read_le(&mut file).or_fail(ErrorKind::Fixture, || format!("reading the transaction in {}", file_path))
+}
+
+/// Lists the `transaction_N` fixtures in `dir` as `(N, path)`, in order of `N`.
+pub(crate) fn list_transactions(dir: &str) -> Result<Vec<(u32, String)>> {
+    let mut transactions = Vec::new();
+    for entry in std::fs::read_dir(dir).or_fail(ErrorKind::Fixture, || format!("listing {}", dir))? {
+        let entry = entry.or_fail(ErrorKind::Fixture, || format!("listing {}", dir))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(Ok(number)) = name.strip_prefix("transaction_").map(|number| number.parse::<u32>()) {
+            transactions.push((number, format!("{}/{}", dir, name)));
+        }
+    }
+    transactions.sort();
+    Ok(transactions)
+}
+
+/// Writes `block` to `dir/name` so later runs can replay it with `load_cached_block`.
+pub(crate) fn cache_block<N: Network>(dir: &str, name: &str, block: &Block<N>) -> Result<()> {
+    tag_fixture_network::<N>(dir)?;
+    let file_path = format!("{}/{}", dir, name);
+    let block_bytes = block.to_bytes_le().or_fail(ErrorKind::Fixture, || format!("serializing the block for {}", file_path))?;
+    std::fs::write(&file_path, block_bytes).or_fail(ErrorKind::Io, || format!("writing {}", file_path))
+}
+
+/// Replays the cached `child`, `parent` and `grandfather` deployment blocks from `dir`.
+pub(crate) fn load_spammer_blocks<N: Network, C: ConsensusStorage<N>>(ledger: &Ledger<N, C>, dir: &str) -> Result<()> {
+    check_fixture_network::<N>(dir)?;
+    for name in ["block_child", "block_parent", "block_grandfather"] {
+        load_cached_block(ledger, dir, name)?;
+    }
+    Ok(())
+}
+
+/// The ledger every verification mode starts from: either the snapshot in `snapshot_dir`, or a fresh ledger with the
+/// cached spammer deployments, any preloaded mapping state and any pool funding replayed on top.
+pub(crate) fn load_fixture_env<N: Network, C: ConsensusStorage<N>>(
+    rng: &mut (impl Rng + CryptoRng),
+    dev: Option<u16>,
+    snapshot_dir: Option<&str>,
+) -> Result<TestEnv<N, C>> {
+    if let Some(snapshot_dir) = snapshot_dir {
+        return crate::snapshot::open_snapshot::<N, C>(snapshot_dir, dev).context(|| format!("opening the snapshot in {}", snapshot_dir));
+    }
+
+    let transactions_dir = "./transactions";
+    let env = sample_test_env::<N, C>(rng, dev)?;
+    load_spammer_blocks(&env.ledger, transactions_dir)?;
+
+    // Replay any preloaded mapping state so finalize runs against a non-empty store.
+    let preloaded_entries = crate::preload::load_preloaded_state(&env.ledger, transactions_dir)?;
+    println!("Preloaded entries: {}", preloaded_entries);
+
+    // Fund the account pool, if it has been set up.
+    let pool_size = crate::accounts::load_funding_blocks(&env.ledger, transactions_dir)?;
+    if pool_size > 0 {
+        println!("Funded pool accounts: {}", pool_size);
+    }
+
+    Ok(env)
+}
@@ -0,0 +1,128 @@
+use crate::error::{Error, ErrorKind, OrFail, Result};
+use crate::programs::FinalizeOp;
+use snarkvm::prelude::{Address, Network, TestRng, ToBits, Uniform, Value};
+use std::str::FromStr;
+
+/// A mapping entry that must hold in the finalize store once a workload has been finalized.
+#[derive(Clone, Debug)]
+pub(crate) struct MappingExpectation {
+    pub program_id: String,
+    pub mapping: String,
+    pub key: String,
+    // `None` means the key must not be present in the mapping.
+    pub value: Option<String>,
+}
+
+impl MappingExpectation {
+    pub(crate) fn new(program_id: &str, mapping: &str, key: &str, value: Option<&str>) -> Self {
+        Self {
+            program_id: program_id.to_string(),
+            mapping: mapping.to_string(),
+            key: key.to_string(),
+            value: value.map(|v| v.to_string()),
+        }
+    }
+}
+
+/// How to produce one input of a workload's function for each generated transaction.
+#[derive(Clone, Debug)]
+pub(crate) enum InputSpec {
+    /// The same literal for every transaction, e.g. `5u64`.
+    Literal(String),
+    /// A fresh random `u64` for every transaction, used as a mapping key.
+    RandomU64,
+    /// A fresh random `u64` below the bound, e.g. to hit keys written by `preload`.
+    RandomU64Below(u64),
+}
+
+/// A function to spam, along with the ledger state we expect after its transactions are finalized.
+#[derive(Clone, Debug)]
+pub(crate) struct Workload {
+    pub program_id: String,
+    pub function_name: String,
+    pub inputs: Vec<InputSpec>,
+    pub expected_mappings: Vec<MappingExpectation>,
+}
+
+impl Workload {
+    /// Samples the inputs for one execution of the workload's function.
+    pub(crate) fn sample_inputs<N: Network>(&self, rng: &mut TestRng) -> Result<Vec<Value<N>>> {
+        self.inputs
+            .iter()
+            .map(|input| {
+                let literal = match input {
+                    InputSpec::Literal(literal) => literal.clone(),
+                    InputSpec::RandomU64 => format!("{}u64", u64::rand(rng)),
+                    InputSpec::RandomU64Below(bound) => format!("{}u64", u64::rand(rng) % bound),
+                };
+                Value::from_str(&literal).or_fail(ErrorKind::Config, || {
+                    format!("parsing input `{}` of {}/{}", literal, self.program_id, self.function_name)
+                })
+            })
+            .collect()
+    }
+}
+
+/// `grandfather_spammer.aleo/outer_most_call`, which ends up writing `i` into `child_spammer.aleo/map[i]` for `i` in 0..16.
+pub(crate) fn outer_most_call() -> Workload {
+    let expected_mappings = (0..16u8)
+        .map(|i| {
+            let entry = format!("{}u8", i);
+            MappingExpectation::new("child_spammer.aleo", "map", &entry, Some(&entry))
+        })
+        .collect();
+
+    Workload {
+        program_id: "grandfather_spammer.aleo".to_string(),
+        function_name: "outer_most_call".to_string(),
+        inputs: Vec::new(),
+        expected_mappings,
+    }
+}
+
+/// `spam` on a program generated by `programs::finalize_mix`, called with a random `u64` key.
+pub(crate) fn finalize_mix(program_id: &str, ops: &[FinalizeOp]) -> Workload {
+    let mut expected_mappings = vec![MappingExpectation::new(program_id, "map", "0u8", Some("0u8"))];
+    let num_sets = ops.iter().filter(|op| **op == FinalizeOp::Set).count();
+    for i in 0..num_sets {
+        let entry = format!("{}u8", FinalizeOp::set_key(i));
+        expected_mappings.push(MappingExpectation::new(program_id, "map", &entry, Some(&entry)));
+    }
+    // The scratch key is always removed again by the end of finalize.
+    if ops.contains(&FinalizeOp::Remove) {
+        expected_mappings.push(MappingExpectation::new(program_id, "map", "255u8", None));
+    }
+
+    Workload {
+        program_id: program_id.to_string(),
+        function_name: "spam".to_string(),
+        inputs: vec![InputSpec::RandomU64],
+        expected_mappings,
+    }
+}
+
+/// The `large_map` entries the `RandomSet` ops of a `programs::finalize_mix` program write when `caller` calls `spam`
+/// with `input`. Each is `{ owner, counter, digest }` at key `input + i`, `i` being the op's index, so it can be
+/// computed here like the VM does.
+pub(crate) fn random_set_expectations<N: Network>(
+    program_id: &str,
+    ops: &[FinalizeOp],
+    caller: &Address<N>,
+    input: &Value<N>,
+) -> Result<Vec<MappingExpectation>> {
+    let describe = || format!("computing the large_map entries {}/spam sets for {}", program_id, input);
+    let input = input.to_string();
+    let input = input.strip_suffix("u64").and_then(|input| input.parse::<u64>().ok()).ok_or_else(|| Error::new(ErrorKind::Config, describe()))?;
+    ops.iter()
+        .enumerate()
+        .filter(|(_, op)| **op == FinalizeOp::RandomSet)
+        .map(|(i, _)| {
+            let key = input.wrapping_add(i as u64);
+            // `hash.bhp256` hashes the bits of the key as a value, type and size included.
+            let key_value = Value::<N>::from_str(&format!("{}u64", key)).or_fail(ErrorKind::Config, describe)?;
+            let digest = N::hash_bhp256(&key_value.to_bits_le()).or_fail(ErrorKind::Config, describe)?;
+            let entry = format!("{{ owner: {}, counter: {}u64, digest: {} }}", caller, key, digest);
+            Ok(MappingExpectation::new(program_id, "large_map", &format!("{}u64", key), Some(&entry)))
+        })
+        .collect()
+}